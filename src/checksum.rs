@@ -0,0 +1,51 @@
+// a small FNV-1a hash, used to check that replays and levels haven't changed.
+// it's written out by hand so that it's the same on every platform and build
+#[derive(Copy, Clone)]
+pub struct Checksum
+{
+    hash: u64,
+}
+
+const OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+const PRIME: u64 = 0x0000_0100_0000_01b3;
+
+impl Checksum
+{
+    pub fn new() -> Checksum
+    {
+        Checksum
+        {
+            hash: OFFSET,
+        }
+    }
+
+    pub fn bytes(&mut self, bytes: &[u8])
+    {
+        for b in bytes
+        {
+            self.hash ^= *b as u64;
+            self.hash = self.hash.wrapping_mul(PRIME);
+        }
+    }
+
+    pub fn u32(&mut self, v: u32)
+    {
+        self.bytes(&v.to_le_bytes());
+    }
+
+    pub fn u64(&mut self, v: u64)
+    {
+        self.bytes(&v.to_le_bytes());
+    }
+
+    // floats are hashed by their bits, so any drift at all shows up
+    pub fn f32(&mut self, v: f32)
+    {
+        self.u32(v.to_bits());
+    }
+
+    pub fn finish(&self) -> u64
+    {
+        self.hash
+    }
+}
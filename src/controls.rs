@@ -0,0 +1,299 @@
+use ggez::
+{
+    Context,
+    GameResult,
+    input::
+    {
+        keyboard::KeyCode,
+        mouse::MouseButton,
+    },
+};
+
+use crate::BOARD_WIDTH;
+use crate::
+{
+    state::State,
+    menu::Menu,
+    input::{self, Action, Bindings, MouseControl},
+    shared::Shared,
+    ui::{TextRect, Button, focus::{self, FocusKey}},
+};
+
+const BORDER: f32 = 25.0;
+const BUTTON_BORDER: f32 = 15.0;
+const SIZE: f32 = 35.0;
+const HINT_SIZE: f32 = 14.0;
+
+const ROW_BORDER: f32 = 8.0;
+const ROW_SIZE: f32 = 20.0;
+const ROW_SEP: f32 = 6.0;
+// the keys buttons line up on their left, after the action names
+const KEYS_X: f32 = 180.0;
+
+// rebinds the keys for each action, the changes are saved when it's left
+pub struct Controls
+{
+    title: TextRect,
+    hint: TextRect,
+    labels: Vec<TextRect>,
+    keys: Vec<Button>,
+    mouse_label: TextRect,
+    mouse: Button,
+    reset: Button,
+    back: Button,
+
+    // the action that's waiting for a key
+    listening: Option<Action>,
+}
+
+impl Controls
+{
+    pub fn new(ctx: &mut Context, shared: &Shared) -> GameResult<Controls>
+    {
+        let title = TextRect::new(ctx, 0.0, BORDER, "Controls", None, SIZE, None)
+            .center_x(BOARD_WIDTH);
+
+        let hint = TextRect::new(ctx, 0.0, title.rect().bottom() + ROW_SEP,
+            "pick an action, then press a key to add or remove it",
+            None, HINT_SIZE, None)
+            .add_line(ctx, "(backspace clears it, escape stops)", HINT_SIZE)
+            .center_x(BOARD_WIDTH);
+
+        let mut labels = Vec::new();
+        let mut keys = Vec::new();
+        let mut y = hint.rect().bottom() + BORDER;
+        for action in Action::ALL.iter()
+        {
+            let b = Controls::keys_button(ctx, &shared.settings.bindings, *action, false, y)?;
+            // the name is lined up with the middle of its button
+            let mut label = TextRect::new(ctx, BORDER, 0.0, action.label(), None, ROW_SIZE, None);
+            let h = label.rect().h;
+            label.move_to(BORDER, b.rect().y + b.rect().h / 2.0 - h / 2.0);
+
+            y = b.rect().bottom() + ROW_SEP;
+            labels.push(label);
+            keys.push(b);
+        }
+
+        let mouse = Controls::mouse_button(ctx, shared.settings.mouse, y)?;
+        let mut mouse_label = TextRect::new(ctx, BORDER, 0.0, "Mouse", None, ROW_SIZE, None);
+        let h = mouse_label.rect().h;
+        mouse_label.move_to(BORDER, mouse.rect().y + mouse.rect().h / 2.0 - h / 2.0);
+        let y = mouse.rect().bottom() + ROW_SEP;
+
+        // reset and back are side by side
+        let reset_text = TextRect::new(ctx, 0.0, 0.0, "Reset", None, SIZE, None);
+        let mut reset = Button::new(ctx, reset_text, BUTTON_BORDER)?;
+        let back_text = TextRect::new(ctx, 0.0, 0.0, "Back", None, SIZE, None);
+        let mut back = Button::new(ctx, back_text, BUTTON_BORDER)?;
+
+        let y = y - ROW_SEP + BORDER;
+        let x = BOARD_WIDTH / 2.0 - (reset.rect().w + BORDER + back.rect().w) / 2.0;
+        reset.move_to(x, y);
+        back.move_to(x + reset.rect().w + BORDER, y);
+
+        Ok(Controls
+        {
+            title: title,
+            hint: hint,
+            labels: labels,
+            keys: keys,
+            mouse_label: mouse_label,
+            mouse: mouse,
+            reset: reset,
+            back: back,
+
+            listening: None,
+        })
+    }
+
+    // the button with an action's keys on it, or a prompt if it's waiting for one
+    fn keys_button(ctx: &mut Context, bindings: &Bindings, action: Action, listening: bool, y: f32)
+        -> GameResult<Button>
+    {
+        let text = if listening
+        {
+            "press a key...".to_string()
+        }
+        else
+        {
+            bindings.describe(action)
+        };
+
+        let t = TextRect::new(ctx, KEYS_X + ROW_BORDER, y + ROW_BORDER, text, None, ROW_SIZE, None);
+        Button::new(ctx, t, ROW_BORDER)
+    }
+
+    // whether the mouse controls the paddle, clicking it goes through the choices
+    fn mouse_button(ctx: &mut Context, mouse: MouseControl, y: f32) -> GameResult<Button>
+    {
+        let t = TextRect::new(ctx, KEYS_X + ROW_BORDER, y + ROW_BORDER, mouse.label(), None,
+            ROW_SIZE, None);
+        Button::new(ctx, t, ROW_BORDER)
+    }
+
+    // remakes every keys button, for after the bindings or what's listening changed
+    fn refresh(&mut self, ctx: &mut Context, shared: &Shared) -> GameResult<()>
+    {
+        for (i, action) in Action::ALL.iter().enumerate()
+        {
+            let y = self.keys[i].rect().y;
+            let focused = self.keys[i].focused();
+            self.keys[i] = Controls::keys_button(ctx, &shared.settings.bindings, *action,
+                self.listening == Some(*action), y)?;
+            self.keys[i].set_focused(focused);
+        }
+
+        Ok(())
+    }
+
+    pub fn update(&mut self, ctx: &mut Context, shared: &mut Shared) -> GameResult<Option<State>>
+    {
+        let clicked = self.keys.iter()
+            .position(|b| b.click())
+            .map(|i| Action::ALL[i]);
+        if let Some(action) = clicked
+        {
+            if self.listening != Some(action)
+            {
+                self.listening = Some(action);
+                self.refresh(ctx, shared)?;
+            }
+        }
+
+        // remade rather than reset, since its text changes
+        if self.mouse.click()
+        {
+            shared.settings.mouse = shared.settings.mouse.next();
+            let y = self.mouse.rect().y;
+            let focused = self.mouse.focused();
+            self.mouse = Controls::mouse_button(ctx, shared.settings.mouse, y)?;
+            self.mouse.set_focused(focused);
+        }
+
+        if self.reset.click()
+        {
+            self.reset.reset();
+            self.listening = None;
+            shared.settings.bindings = Bindings::default();
+            self.refresh(ctx, shared)?;
+        }
+        if self.back.click()
+        {
+            shared.settings.write();
+            return Ok(Some(State::Menu(Menu::new(ctx)?)))
+        }
+
+        Ok(None)
+    }
+
+    pub fn listening(&self) -> bool
+    {
+        self.listening.is_some()
+    }
+
+    // the buttons in the order the keyboard goes through them
+    fn buttons(&mut self) -> Vec<&mut Button>
+    {
+        let mut v = self.keys.iter_mut().collect::<Vec<_>>();
+        v.push(&mut self.mouse);
+        v.push(&mut self.reset);
+        v.push(&mut self.back);
+        v
+    }
+
+    // while an action is waiting for a key, the keys go to it rather than moving
+    // around the screen. escape goes back otherwise
+    pub fn key_down(&mut self, ctx: &mut Context, shared: &mut Shared, key: KeyCode, repeat: bool)
+        -> GameResult<()>
+    {
+        let action = match self.listening
+        {
+            Some(action) => action,
+            None =>
+            {
+                if focus::key_down(&mut self.buttons(), key, repeat) == FocusKey::Back
+                {
+                    self.back.press();
+                }
+                return Ok(())
+            },
+        };
+
+        // the key that started listening might still be held down
+        if repeat
+        {
+            return Ok(())
+        }
+
+        match key
+        {
+            KeyCode::Escape => self.listening = None,
+            KeyCode::Back => shared.settings.bindings.set_keys(action, Vec::new()),
+            // keys that can't be written in the settings file can't be bound
+            _ => if input::bindable(key)
+            {
+                shared.settings.bindings.toggle(action, key);
+                self.listening = None;
+            },
+        }
+
+        self.refresh(ctx, shared)
+    }
+
+    pub fn key_up(&mut self, key: KeyCode)
+    {
+        focus::key_up(&mut self.buttons(), key);
+    }
+
+    pub fn mouse_move(&mut self, x: f32, y: f32)
+    {
+        for b in self.keys.iter_mut()
+        {
+            b.mouse_move(x, y);
+        }
+        self.mouse.mouse_move(x, y);
+        self.reset.mouse_move(x, y);
+        self.back.mouse_move(x, y);
+    }
+
+    pub fn mouse_down(&mut self, button: MouseButton, x: f32, y: f32)
+    {
+        for b in self.keys.iter_mut()
+        {
+            b.mouse_down(button, x, y);
+        }
+        self.mouse.mouse_down(button, x, y);
+        self.reset.mouse_down(button, x, y);
+        self.back.mouse_down(button, x, y);
+    }
+
+    pub fn mouse_up(&mut self, button: MouseButton)
+    {
+        for b in self.keys.iter_mut()
+        {
+            b.mouse_up(button);
+        }
+        self.mouse.mouse_up(button);
+        self.reset.mouse_up(button);
+        self.back.mouse_up(button);
+    }
+
+    pub fn draw(&self, ctx: &mut Context) -> GameResult<()>
+    {
+        self.title.draw(ctx)?;
+        self.hint.draw(ctx)?;
+        for t in &self.labels
+        {
+            t.draw(ctx)?;
+        }
+        for b in &self.keys
+        {
+            b.draw(ctx)?;
+        }
+        self.mouse_label.draw(ctx)?;
+        self.mouse.draw(ctx)?;
+        self.reset.draw(ctx)?;
+        self.back.draw(ctx)
+    }
+}
@@ -0,0 +1,285 @@
+use ggez::
+{
+    Context,
+    GameResult,
+    input::
+    {
+        keyboard::KeyCode,
+        mouse::MouseButton,
+    },
+};
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::BOARD_WIDTH;
+use crate::
+{
+    state::State,
+    menu::Menu,
+    game::{Game, Mode},
+    name_entry::NameEntry,
+    save::DailyRecord,
+    scores::Table,
+    shared::Shared,
+    ui::{TextRect, Button, focus::{self, FocusKey}},
+};
+
+const BORDER: f32 = 25.0;
+const BUTTON_BORDER: f32 = 15.0;
+const SIZE: f32 = 35.0;
+const INFO_SIZE: f32 = 20.0;
+const HISTORY_SIZE: f32 = 16.0;
+const HISTORY_SEP: f32 = 4.0;
+
+// how many past days are shown
+const HISTORY: usize = 7;
+
+// today, as the number of days since 1970. days start at midnight UTC, so the
+// challenge is the same everywhere at once
+pub fn today() -> u32
+{
+    SystemTime::now().duration_since(UNIX_EPOCH)
+        .map(|d| (d.as_secs() / (24 * 60 * 60)) as u32)
+        .unwrap_or(0)
+}
+
+// a day from today() as 'year-month-day'
+pub fn date(day: u32) -> String
+{
+    // from the days since 1970 to the days since 0000-03-01, so leap days come at the
+    // end of each year, then into 400 year eras which always have the same number of days
+    let z = day + 719_468;
+    let era = z / 146_097;
+    let day_of_era = z % 146_097;
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36_524
+        - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    // months counted from march
+    let m = (5 * day_of_year + 2) / 153;
+
+    let d = day_of_year - (153 * m + 2) / 5 + 1;
+    let month = if m < 10 { m + 3 } else { m - 9 };
+    let year = era * 400 + year_of_era + if month <= 2 { 1 } else { 0 };
+
+    format!("{:04}-{:02}-{:02}", year, month, d)
+}
+
+// today's challenge, which can only be played once, and how the past days went
+pub struct Daily
+{
+    title: TextRect,
+    info: TextRect,
+    // asks for a name, if the challenge was just played and made its high score table
+    name_entry: Option<NameEntry>,
+    play: Button,
+    history: Vec<TextRect>,
+    back: Button,
+
+    day: u32,
+}
+
+impl Daily
+{
+    // score is the high score table and the place in it of a challenge that was just
+    // played, if it made it
+    pub fn new(ctx: &mut Context, shared: &Shared, score: Option<(Table, usize)>)
+        -> GameResult<Daily>
+    {
+        let day = today();
+        let record = shared.save.daily(day);
+
+        let title = TextRect::new(ctx, 0.0, BORDER, "Daily Challenge", None, SIZE, None)
+            .center_x(BOARD_WIDTH);
+
+        let info = TextRect::new(ctx, 0.0, title.rect().bottom() + BORDER,
+            format!("{}: {}", date(day), match record
+            {
+                Some(r) => Daily::result(&r),
+                None => "not played yet".to_string(),
+            }),
+            None, INFO_SIZE, None)
+            .center_x(BOARD_WIDTH);
+
+        let mut bottom = info.rect().bottom();
+
+        let name_entry = match score
+        {
+            Some((table, rank)) =>
+            {
+                let entry = NameEntry::new(ctx, shared, bottom + BORDER / 2.0, table, rank)?;
+                bottom = entry.bottom();
+                Some(entry)
+            },
+            None => None,
+        };
+
+        let play_text = TextRect::new(ctx, 0.0, bottom + BORDER,
+            "Play", None, SIZE, None)
+            .center_x(BOARD_WIDTH);
+        let mut play = Button::new(ctx, play_text, BUTTON_BORDER)?;
+        // there's one attempt a day
+        play.set_locked(record.is_some());
+
+        let mut y = play.rect().bottom() + BORDER;
+        let mut history = Vec::new();
+        for (d, r) in shared.save.daily_records().into_iter()
+            .filter(|(d, _)| *d != day)
+            .take(HISTORY)
+        {
+            let t = TextRect::new(ctx, 0.0, y,
+                format!("{}: {}", date(d), Daily::result(&r)),
+                None, HISTORY_SIZE, None)
+                .center_x(BOARD_WIDTH);
+            y = t.rect().bottom() + HISTORY_SEP;
+            history.push(t);
+        }
+
+        let back_text = TextRect::new(ctx, 0.0, y - HISTORY_SEP + BORDER,
+            "Back", None, SIZE, None)
+            .center_x(BOARD_WIDTH);
+        let back = Button::new(ctx, back_text, BUTTON_BORDER)?;
+
+        Ok(Daily
+        {
+            title: title,
+            info: info,
+            name_entry: name_entry,
+            play: play,
+            history: history,
+            back: back,
+
+            day: day,
+        })
+    }
+
+    fn result(r: &DailyRecord) -> String
+    {
+        if !r.finished
+        {
+            "not finished".to_string()
+        }
+        else
+        {
+            format!("{} | time: {} | score: {} | lives: {}",
+                if r.cleared { "clear" } else { "lose" }, r.time, r.score, r.lives)
+        }
+    }
+
+    pub fn update(&mut self, ctx: &mut Context, shared: &mut Shared) -> GameResult<Option<State>>
+    {
+        if self.play.click()
+        {
+            // the attempt is saved as soon as it starts, so quitting doesn't give
+            // another go
+            shared.save.start_daily(self.day);
+            shared.save.write();
+
+            return Ok(Some(State::Game(Game::new(ctx, shared, Mode::Daily(self.day))?)))
+        }
+        if self.back.click()
+        {
+            // the name is saved on the way out, in case return wasn't pressed
+            if let Some(ref entry) = self.name_entry
+            {
+                entry.save(shared);
+            }
+            return Ok(Some(State::Menu(Menu::new(ctx)?)))
+        }
+
+        Ok(None)
+    }
+
+    // the name box gets the keys while it's being typed in. otherwise escape goes back
+    // to the main menu
+    pub fn key_down(&mut self, ctx: &mut Context, shared: &mut Shared, key: KeyCode, repeat: bool)
+    {
+        let typing = match self.name_entry
+        {
+            Some(ref mut entry) => entry.key_down(ctx, shared, key, repeat),
+            None => false,
+        };
+
+        if !typing
+            && focus::key_down(&mut [&mut self.play, &mut self.back], key, repeat) == FocusKey::Back
+        {
+            self.back.press();
+        }
+    }
+
+    pub fn key_up(&mut self, key: KeyCode)
+    {
+        focus::key_up(&mut [&mut self.play, &mut self.back], key);
+    }
+
+    pub fn text_input(&mut self, ctx: &mut Context, ch: char)
+    {
+        if let Some(ref mut entry) = self.name_entry
+        {
+            entry.text_input(ctx, ch);
+        }
+    }
+
+    pub fn mouse_move(&mut self, x: f32, y: f32)
+    {
+        self.play.mouse_move(x, y);
+        self.back.mouse_move(x, y);
+    }
+
+    pub fn mouse_down(&mut self, button: MouseButton, x: f32, y: f32)
+    {
+        if let Some(ref mut entry) = self.name_entry
+        {
+            entry.mouse_down(button, x, y);
+        }
+        self.play.mouse_down(button, x, y);
+        self.back.mouse_down(button, x, y);
+    }
+
+    pub fn mouse_up(&mut self, button: MouseButton)
+    {
+        self.play.mouse_up(button);
+        self.back.mouse_up(button);
+    }
+
+    pub fn draw(&self, ctx: &mut Context) -> GameResult<()>
+    {
+        self.title.draw(ctx)?;
+        self.info.draw(ctx)?;
+        if let Some(ref entry) = self.name_entry
+        {
+            entry.draw(ctx)?;
+        }
+        self.play.draw(ctx)?;
+        for t in &self.history
+        {
+            t.draw(ctx)?;
+        }
+        self.back.draw(ctx)
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    #[test]
+    fn the_first_day_is_1970_01_01()
+    {
+        assert_eq!(date(0), "1970-01-01");
+    }
+
+    #[test]
+    fn a_leap_day_is_its_own_date()
+    {
+        assert_eq!(date(11_016), "2000-02-29");
+        assert_eq!(date(11_017), "2000-03-01");
+    }
+
+    #[test]
+    fn a_new_year_follows_the_last_day_of_the_old_one()
+    {
+        assert_eq!(date(19_722), "2023-12-31");
+        assert_eq!(date(19_723), "2024-01-01");
+    }
+}
@@ -0,0 +1,280 @@
+use ggez::
+{
+    Context,
+    GameResult,
+    input::
+    {
+        keyboard::KeyCode,
+        mouse::MouseButton,
+    },
+};
+
+use crate::BOARD_WIDTH;
+use crate::
+{
+    state::State,
+    menu::Menu,
+    game::{Game, Mode},
+    rng::Rng,
+    shared::Shared,
+    ui::{TextRect, TextInput, Button, focus::{self, FocusKey}},
+};
+
+const BORDER: f32 = 25.0;
+const BUTTON_BORDER: f32 = 15.0;
+const SIZE: f32 = 35.0;
+
+const SEED_BORDER: f32 = 8.0;
+const SEED_SIZE: f32 = 20.0;
+const SEED_SEP: f32 = 10.0;
+
+// only the best seeds are shown
+const SHOWN: usize = 5;
+
+// a seed is written as 8 hex digits, like at the top of the game
+const SEED_LEN: usize = 8;
+
+// starts endless mode, on a new seed, a typed in one or one that's been played before
+pub struct EndlessSelect
+{
+    title: TextRect,
+    new_seed: Button,
+    seed_input: TextInput,
+    play_seed: Button,
+    seeds: Vec<(Button, u32)>,
+    back: Button,
+}
+
+// reads a typed in seed, in hex like it's shown. gives None if it isn't one
+pub fn parse_seed(s: &str) -> Option<u32>
+{
+    let s = s.trim();
+    if s.is_empty() || s.len() > SEED_LEN || !s.chars().all(|c| c.is_ascii_hexdigit())
+    {
+        return None
+    }
+
+    u32::from_str_radix(s, 16).ok()
+}
+
+impl EndlessSelect
+{
+    pub fn new(ctx: &mut Context, shared: &Shared) -> GameResult<EndlessSelect>
+    {
+        let title = TextRect::new(ctx, 0.0, BORDER, "Endless", None, SIZE, None)
+            .center_x(BOARD_WIDTH);
+
+        let new_seed_text = TextRect::new(ctx, 0.0, title.rect().bottom() + BORDER,
+            "New Seed", None, SIZE, None)
+            .center_x(BOARD_WIDTH);
+        let new_seed = Button::new(ctx, new_seed_text, BUTTON_BORDER)?;
+
+        // the seed box and its button sit side by side, centered together
+        let mut seed_input = TextInput::new(ctx, 0.0, 0.0, "", SEED_LEN, SEED_SIZE, SEED_BORDER)?;
+        let play_seed_text = TextRect::new(ctx, 0.0, 0.0, "Play Seed", None, SEED_SIZE, None);
+        let mut play_seed = Button::new(ctx, play_seed_text, SEED_BORDER)?;
+        let x = BOARD_WIDTH / 2.0 - (seed_input.rect().w + SEED_SEP + play_seed.rect().w) / 2.0;
+        let y = new_seed.rect().bottom() + BORDER;
+        seed_input.move_to(x, y);
+        play_seed.move_to(seed_input.rect().right() + SEED_SEP, y);
+
+        let mut seeds = Vec::new();
+        let mut y = f32::max(seed_input.rect().bottom(), play_seed.rect().bottom()) + BORDER;
+        for (seed, r) in shared.save.endless_records().into_iter().take(SHOWN)
+        {
+            let t = TextRect::new(ctx, 0.0, 0.0,
+                format!("{:08x}: {} (wave {})", seed, r.best_score, r.wave),
+                None, SEED_SIZE, None);
+            let mut b = Button::new(ctx, t, SEED_BORDER)?;
+            b.move_to(BOARD_WIDTH / 2.0 - b.rect().w / 2.0, y);
+            y = b.rect().bottom() + SEED_SEP;
+
+            seeds.push((b, seed));
+        }
+
+        let back_text = TextRect::new(ctx, 0.0, y - SEED_SEP + BORDER,
+            "Back", None, SIZE, None)
+            .center_x(BOARD_WIDTH);
+        let back = Button::new(ctx, back_text, BUTTON_BORDER)?;
+
+        Ok(EndlessSelect
+        {
+            title: title,
+            new_seed: new_seed,
+            seed_input: seed_input,
+            play_seed: play_seed,
+            seeds: seeds,
+            back: back,
+        })
+    }
+
+    pub fn update(&mut self, ctx: &mut Context, shared: &mut Shared) -> GameResult<Option<State>>
+    {
+        if self.new_seed.click()
+        {
+            let seed = Rng::random_seed() as u32;
+            return Ok(Some(State::Game(Game::new(ctx, shared, Mode::Endless(seed))?)))
+        }
+        if self.play_seed.click()
+        {
+            match parse_seed(self.seed_input.value())
+            {
+                Some(seed) => return Ok(Some(State::Game(Game::new(ctx, shared, Mode::Endless(seed))?))),
+                // without a seed to play, it goes to the box to type one
+                None =>
+                {
+                    self.play_seed.reset();
+                    self.seed_input.set_focused(true);
+                },
+            }
+        }
+        // a seed that's been played before is played again from its first wave
+        for (b, seed) in &self.seeds
+        {
+            if b.click()
+            {
+                return Ok(Some(State::Game(Game::new(ctx, shared, Mode::Endless(*seed))?)))
+            }
+        }
+        if self.back.click()
+        {
+            return Ok(Some(State::Menu(Menu::new(ctx)?)))
+        }
+
+        Ok(None)
+    }
+
+    // the buttons in the order the keyboard goes through them
+    fn buttons(&mut self) -> Vec<&mut Button>
+    {
+        let mut v = vec![&mut self.new_seed, &mut self.play_seed];
+        v.extend(self.seeds.iter_mut().map(|(b, _)| b));
+        v.push(&mut self.back);
+        v
+    }
+
+    // while a seed is being typed return plays it, escape stops typing and the
+    // arrow keys and tab go back to the buttons. otherwise escape goes back to the
+    // main menu
+    pub fn key_down(&mut self, ctx: &mut Context, key: KeyCode, repeat: bool)
+    {
+        if self.seed_input.focused()
+        {
+            match key
+            {
+                KeyCode::Return | KeyCode::NumpadEnter =>
+                {
+                    if !repeat
+                    {
+                        self.play_seed.press();
+                    }
+                    return
+                },
+                KeyCode::Escape =>
+                {
+                    self.seed_input.set_focused(false);
+                    return
+                },
+                KeyCode::Up | KeyCode::Down | KeyCode::Tab => self.seed_input.set_focused(false),
+                _ =>
+                {
+                    self.seed_input.key_down(ctx, key);
+                    return
+                },
+            }
+        }
+
+        if focus::key_down(&mut self.buttons(), key, repeat) == FocusKey::Back
+        {
+            self.back.press();
+        }
+    }
+
+    pub fn key_up(&mut self, key: KeyCode)
+    {
+        focus::key_up(&mut self.buttons(), key);
+    }
+
+    // only hex digits go in the seed box
+    pub fn text_input(&mut self, ctx: &mut Context, ch: char)
+    {
+        if ch.is_ascii_hexdigit()
+        {
+            self.seed_input.text_input(ctx, ch);
+        }
+    }
+
+    pub fn mouse_move(&mut self, x: f32, y: f32)
+    {
+        self.new_seed.mouse_move(x, y);
+        self.play_seed.mouse_move(x, y);
+        for (b, _) in self.seeds.iter_mut()
+        {
+            b.mouse_move(x, y);
+        }
+        self.back.mouse_move(x, y);
+    }
+
+    pub fn mouse_down(&mut self, button: MouseButton, x: f32, y: f32)
+    {
+        self.new_seed.mouse_down(button, x, y);
+        self.seed_input.mouse_down(button, x, y);
+        self.play_seed.mouse_down(button, x, y);
+        for (b, _) in self.seeds.iter_mut()
+        {
+            b.mouse_down(button, x, y);
+        }
+        self.back.mouse_down(button, x, y);
+    }
+
+    pub fn mouse_up(&mut self, button: MouseButton)
+    {
+        self.new_seed.mouse_up(button);
+        self.play_seed.mouse_up(button);
+        for (b, _) in self.seeds.iter_mut()
+        {
+            b.mouse_up(button);
+        }
+        self.back.mouse_up(button);
+    }
+
+    pub fn draw(&self, ctx: &mut Context) -> GameResult<()>
+    {
+        self.title.draw(ctx)?;
+        self.new_seed.draw(ctx)?;
+        self.seed_input.draw(ctx)?;
+        self.play_seed.draw(ctx)?;
+        for (b, _) in &self.seeds
+        {
+            b.draw(ctx)?;
+        }
+        self.back.draw(ctx)
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    #[test]
+    fn a_seed_is_read_in_hex()
+    {
+        assert_eq!(parse_seed("0a1b2c3d"), Some(0x0a1b2c3d));
+        assert_eq!(parse_seed("FFFFFFFF"), Some(0xffffffff));
+        assert_eq!(parse_seed(" 7 "), Some(7));
+        // it reads back the way it's shown
+        assert_eq!(parse_seed(&format!("{:08x}", 0x00c0ffee)), Some(0x00c0ffee));
+    }
+
+    #[test]
+    fn anything_else_isnt_a_seed()
+    {
+        assert_eq!(parse_seed(""), None);
+        assert_eq!(parse_seed("xyz"), None);
+        assert_eq!(parse_seed("+1"), None);
+        assert_eq!(parse_seed("-1"), None);
+        // too long for a u32
+        assert_eq!(parse_seed("123456789"), None);
+    }
+}
@@ -1,25 +1,21 @@
-use ggez::
-{
-    Context,
-    GameResult,
-    graphics::
-    {
-        self,
-        Mesh,
-        DrawMode,
-        DrawParam,
-    },
-};
-
 use crate::{BOARD_WIDTH, BOARD_HEIGHT, FOREHEAD};
 
 use super::paddle::Paddle;
-use super::bricks::Bricks;
+use super::bricks::{Bricks, Brick};
 use super::utils;
 
 pub const BALL_SIZE: f32 = 5f32;
-const BALL_SPEED: f32 = 2.5f32;
+// in pixels per second
+pub const BALL_SPEED: f32 = 150f32;
+// the most things the ball can bounce off of in one step
+const MAX_BOUNCES: usize = 8;
+// how far the ball is kept away from whatever it bounced off of
+const SEPARATION: f32 = 0.01;
+// how far to the side a ball released from the edge of the paddle goes, for every
+// pixel it goes up
+const RELEASE_SIDE: f32 = 0.8;
 
+#[derive(Clone)]
 pub struct Ball
 {
     size: f32,
@@ -27,171 +23,430 @@ pub struct Ball
     dir: [f32; 2],
     speed: f32,
 
-    mesh: Mesh,
+    // the position before the last step
+    prev_pos: [f32; 2],
+
+    // if the ball is stuck to the paddle, how far it is from the paddle's left side
+    stuck: Option<f32>,
 }
 
 impl Ball
 {
-    pub fn new(ctx: &mut Context, pos: [f32; 2], dir: [f32; 2]) -> GameResult<Ball>
+    pub fn new(pos: [f32; 2], dir: [f32; 2]) -> Ball
     {
-        Ok(Ball
+        Ball
         {
             size: BALL_SIZE,
             pos: pos,
             dir: utils::normalize(dir),
             speed: BALL_SPEED,
 
-            mesh: Mesh::new_circle(
-                ctx,
-                DrawMode::fill(),
-                // the mesh is at (0, 0) because we draw it at the ball position
-                // at draw time
-                [0f32, 0f32],
-                BALL_SIZE,
-                0.1,
-                graphics::BLACK)?,
-        })
+            prev_pos: pos,
+
+            stuck: None,
+        }
     }
 
-    // maybe there's a better way to do this
-    pub fn update(&mut self, paddle: &Paddle, bricks: &mut Bricks) -> UpdateReturn
+    pub fn begin_step(&mut self)
     {
-        let mut collided_brick = false;
+        self.prev_pos = self.pos;
+    }
 
-        // project position
-        let proj =
+    // the position part of the way between the last step and this one
+    pub fn lerp_pos(&self, alpha: f32) -> [f32; 2]
+    {
         [
-            self.pos[0] + self.dir[0] * self.speed,
-            self.pos[1] + self.dir[1] * self.speed
-        ];
+            self.prev_pos[0] + (self.pos[0] - self.prev_pos[0]) * alpha,
+            self.prev_pos[1] + (self.pos[1] - self.prev_pos[1]) * alpha,
+        ]
+    }
 
-        let mut bounce_x_axis = false;
-        let mut bounce_y_axis = false;
+    pub fn pos(&self) -> [f32; 2]
+    {
+        self.pos
+    }
 
-        // bounce off of the walls
-        if proj[0] < self.size || proj[0] + self.size > BOARD_WIDTH
-        {
-            bounce_y_axis = true;
-        }
-        if proj[1] < self.size + FOREHEAD
+    pub fn dir(&self) -> [f32; 2]
+    {
+        self.dir
+    }
+
+    pub fn set_speed(&mut self, speed: f32)
+    {
+        self.speed = speed;
+    }
+
+    pub fn stuck(&self) -> bool
+    {
+        self.stuck.is_some()
+    }
+
+    // a copy of the ball going off at an angle to this one
+    pub fn split(&self, angle: f32) -> Ball
+    {
+        // a stuck ball would go straight up when it's released
+        let dir = if self.stuck.is_some() { [0.0, -1.0] } else { self.dir };
+
+        let mut ball = self.clone();
+        ball.dir = utils::rotate(dir, angle);
+        ball.stuck = None;
+        ball
+    }
+
+    // launches the ball off of the paddle if it's stuck to it, the further
+    // from the middle of the paddle the more it goes to the side
+    pub fn release(&mut self, paddle: &Paddle)
+    {
+        if let Some(offset) = self.stuck.take()
         {
-            bounce_x_axis = true;
+            let side = offset / paddle.rect().w * 2.0 - 1.0;
+            self.dir = utils::normalize([side * RELEASE_SIDE, -1.0]);
         }
+    }
+
+    // the ball is swept along its path, so it can't pass through anything no matter
+    // how fast it goes. each time it touches something it bounces off and carries on
+    // with whatever distance is left of the step
+    // dt is the length of the step in seconds, and if sticky is set the ball sticks to
+    // the top of the paddle instead of bouncing off it
+    pub fn update(&mut self, paddle: &Paddle, bricks: &mut Bricks, dt: f32, sticky: bool)
+        -> UpdateReturn
+    {
+        let mut bricks_hit = 0;
+        let mut points = 0;
+        let mut broken = Vec::new();
+        let mut explosions = Vec::new();
+        let mut paddle_hits = 0;
 
-        if proj[1] - self.size > FOREHEAD + BOARD_HEIGHT
+        // a stuck ball just goes where the paddle goes
+        if let Some(offset) = self.stuck
         {
+            self.pos = [paddle.rect().x + offset, paddle.rect().top() - self.size - SEPARATION];
             return UpdateReturn
             {
-                destroyed_ball: true,
-                collided_brick: collided_brick,
-            };
+                destroyed_ball: false,
+                bricks_hit: bricks_hit,
+                points: points,
+                broken: broken,
+                explosions: explosions,
+                paddle_hits: paddle_hits,
+            }
         }
 
-        match utils::intersect_rect(self.pos, proj, self.size, paddle.rect())
-        {
-            Some(utils::Axis::X) =>
-            {
-                let r = paddle.rect();
+        // how far the ball has left to move this step
+        let step = self.speed * dt;
+        let mut remaining = step;
 
-                // if the projected collision is on the left quarter or right quarter,
-                // bounce the ball on an angle
-                if proj[0] < r.x + r.w * 0.25
-                {
-                    self.dir = utils::bounce_angle(self.dir, [10.0, -2.0]);
-                }
-                else if proj[0] > r.x + r.w * 0.75
-                {
-                    self.dir = utils::bounce_angle(self.dir, [10.0, 2.0]);
-                }
-                else
-                {
-                    bounce_x_axis = true;
-                }
-            },
-            Some(utils::Axis::Y) => bounce_y_axis = true,
-            None => { }
-        }
-        
-        // the ball can only collide with one brick at a time,
-        // we choose the closest one that the projected ball position
-        // collides with
-        let mut dist = ::std::f32::MAX;
-        let mut collision = None;
-        for brick in bricks.bricks().iter_mut() // bricks
+        for _ in 0..MAX_BOUNCES
         {
-            if let Some(b) = brick
+            let delta = utils::mul(self.dir, remaining);
+
+            // find whatever the ball touches first
+            let mut first: Option<(utils::Hit, Target)> = None;
+            let mut consider = |hit: Option<utils::Hit>, target: Target|
             {
-                if let Some(axis) = utils::intersect_rect(self.pos, proj, self.size, b.rect)
+                if let Some(hit) = hit
                 {
-                    let d = utils::dist_to_rect(self.pos, b.rect);
-                    if d < dist
+                    if first.as_ref().map_or(true, |(f, _)| hit.t < f.t)
                     {
-                        dist = d;
-                        collision = Some((axis, brick))
+                        first = Some((hit, target));
                     }
                 }
-            }
-        }
+            };
 
-        if let Some((axis, brick)) = collision
-        {
-            match axis
+            consider(self.sweep_walls(delta), Target::Wall);
+            consider(utils::sweep_circle_rect(self.pos, delta, self.size, paddle.rect()),
+                Target::Paddle);
+            // moving bricks have already moved this step, so the ball is swept from
+            // where it was next to the brick before it moved, by how it moved next to it
+            let left = remaining / step;
+            for (i, brick) in bricks.bricks().iter().enumerate()
             {
-                utils::Axis::X => bounce_x_axis = true,
-                utils::Axis::Y => bounce_y_axis = true,
+                if let Some(b) = brick
+                {
+                    let moved = utils::mul(b.moved, left);
+                    consider(utils::sweep_circle_rect(
+                            utils::add(self.pos, moved),
+                            utils::sub(delta, moved),
+                            self.size,
+                            b.rect),
+                        Target::Brick(i, b.moved));
+                }
             }
 
-            if let Some(b) = brick
+            let (hit, target) = match first
             {
-                b.count -= 1;
-                if b.count == 0
+                Some(first) => first,
+                None =>
                 {
-                    *brick = None;
-                }
-                collided_brick = true;
+                    self.pos = [self.pos[0] + delta[0], self.pos[1] + delta[1]];
+                    break;
+                },
+            };
+
+            // move up to where it touched, and back off a tiny bit so it doesn't
+            // touch the same thing again straight away
+            self.pos =
+            [
+                self.pos[0] + delta[0] * hit.t + hit.normal[0] * SEPARATION,
+                self.pos[1] + delta[1] * hit.t + hit.normal[1] * SEPARATION,
+            ];
+            remaining *= 1.0 - hit.t;
+
+            match target
+            {
+                Target::Paddle if hit.normal == [0.0, -1.0] && sticky =>
+                {
+                    paddle_hits += 1;
+                    self.stuck = Some(self.pos[0] - paddle.rect().x);
+                    break;
+                },
+                Target::Paddle if hit.normal == [0.0, -1.0] =>
+                {
+                    paddle_hits += 1;
+                    let r = paddle.rect();
+
+                    // if the collision is on the left quarter or right quarter,
+                    // bounce the ball on an angle
+                    if self.pos[0] < r.x + r.w * 0.25
+                    {
+                        self.dir = utils::bounce_angle(self.dir, [10.0, -2.0]);
+                    }
+                    else if self.pos[0] > r.x + r.w * 0.75
+                    {
+                        self.dir = utils::bounce_angle(self.dir, [10.0, 2.0]);
+                    }
+                    else
+                    {
+                        self.dir = utils::reflect(self.dir, hit.normal);
+                    }
+                },
+                Target::Brick(i, moved) =>
+                {
+                    // the bricks know what a hit does to them
+                    let damage = bricks.hit(i);
+                    bricks_hit += damage.hits;
+                    points += damage.points;
+                    broken.extend(damage.broken);
+                    explosions.extend(damage.explosions);
+
+                    // bounce off of a moving brick as if it were still, then add its
+                    // velocity back on. the ball keeps its own speed
+                    let brick_v = utils::div(moved, dt);
+                    let v = utils::sub(utils::mul(self.dir, self.speed), brick_v);
+                    let v = utils::add(utils::reflect(v, hit.normal), brick_v);
+                    self.dir = if utils::len(v) > 0.0
+                    {
+                        utils::normalize(v)
+                    }
+                    else
+                    {
+                        utils::reflect(self.dir, hit.normal)
+                    };
+                },
+                _ => self.dir = utils::reflect(self.dir, hit.normal),
             }
         }
 
-        if bounce_x_axis
-        {
-            self.dir = utils::bounce_axis(self.dir, utils::Axis::X);
-        }
-        if bounce_y_axis
+        if self.pos[1] - self.size > FOREHEAD + BOARD_HEIGHT
         {
-            self.dir = utils::bounce_axis(self.dir, utils::Axis::Y);
+            return UpdateReturn
+            {
+                destroyed_ball: true,
+                bricks_hit: bricks_hit,
+                points: points,
+                broken: broken,
+                explosions: explosions,
+                paddle_hits: paddle_hits,
+            };
         }
 
-        self.pos =
-        [
-            self.pos[0] + self.dir[0] * self.speed,
-            self.pos[1] + self.dir[1] * self.speed
-        ];
-
-        // if the ball has found itself inside the paddle, get it out of there!
+        // the sweep treats the paddle as still, but it's moved before the balls are,
+        // so it can move sideways into a ball. balls are also launched from just
+        // inside its top. either way, if the ball has found itself inside the
+        // paddle, get it out of there!
         if let Some(pos) = utils::inside_rect(self.pos, self.size, paddle.rect())
         {
             self.pos = pos;
         }
 
-        return UpdateReturn
+        UpdateReturn
         {
             destroyed_ball: false,
-            collided_brick: collided_brick,
+            bricks_hit: bricks_hit,
+            points: points,
+            broken: broken,
+            explosions: explosions,
+            paddle_hits: paddle_hits,
         }
     }
 
-    pub fn draw(&self, ctx: &mut Context) -> GameResult<()>
+    // the first time the ball touches the left, right or top wall moving by delta.
+    // the bottom is open
+    fn sweep_walls(&self, delta: [f32; 2]) -> Option<utils::Hit>
     {
-        graphics::draw(
-            ctx,
-            &self.mesh,
-            DrawParam::new()
-                .dest(self.pos))
+        let mut first: Option<utils::Hit> = None;
+
+        let walls =
+        [
+            (0, self.size, [1.0, 0.0]),
+            (0, BOARD_WIDTH - self.size, [-1.0, 0.0]),
+            (1, FOREHEAD + self.size, [0.0, 1.0]),
+        ];
+
+        for &(axis, at, normal) in &walls
+        {
+            // only walls the ball is moving towards
+            if delta[axis] * normal[axis] >= 0.0
+            {
+                continue;
+            }
+
+            let t = f32::max(0.0, (at - self.pos[axis]) / delta[axis]);
+            if t <= 1.0 && first.map_or(true, |f| t < f.t)
+            {
+                first = Some(utils::Hit
+                {
+                    t: t,
+                    normal: normal,
+                });
+            }
+        }
+
+        first
     }
 }
 
+// what the ball can bounce off of
+enum Target
+{
+    Wall,
+    Paddle,
+    // the brick, and how far it moved this step
+    Brick(usize, [f32; 2]),
+}
+
 pub struct UpdateReturn
 {
     pub destroyed_ball: bool,
-    pub collided_brick: bool,
+    // how many hits were taken off of bricks this step
+    pub bricks_hit: u32,
+    // the points for those hits
+    pub points: u32,
+    // the bricks that were broken this step, by the ball or by explosions
+    pub broken: Vec<Brick>,
+    // where explosions went off this step
+    pub explosions: Vec<[f32; 2]>,
+    // how many times the ball bounced off the top of the paddle
+    pub paddle_hits: u32,
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::levels::Cell;
+    use crate::game::{BrickKind, Motion};
+    use crate::game::bricks::{BRICK_WIDTH, BRICK_HEIGHT};
+
+    fn bricks(at: &[(f32, f32)], count: u32) -> Bricks
+    {
+        Bricks::new(at.iter().map(|p| (*p, Cell
+        {
+            count: count,
+            drop: None,
+            kind: BrickKind::Normal,
+            motion: Motion::Still,
+        })).collect())
+    }
+
+    #[test]
+    fn a_fast_ball_doesnt_go_through_a_brick()
+    {
+        // the step is far longer than the brick is tall
+        let mut bricks = bricks(&[(200.0, 150.0)], 2);
+        let mut ball = Ball::new([210.0, 300.0], [0.0, -1.0]);
+        ball.set_speed(250.0 / 0.01);
+
+        let r = ball.update(&Paddle::new(), &mut bricks, 0.01, false);
+
+        assert_eq!(r.bricks_hit, 1);
+        assert_eq!(ball.dir(), [0.0, 1.0]);
+        // it went up 125 to touch the brick, and came back down the other 125
+        assert!((ball.pos()[1] - 300.0).abs() < 0.1);
+    }
+
+    #[test]
+    fn a_ball_hitting_a_corner_goes_back_the_way_it_came()
+    {
+        let mut bricks = bricks(&[(200.0, 150.0)], 2);
+        // straight at the bottom left corner
+        let d = BALL_SIZE / f32::sqrt(2.0);
+        let mut ball = Ball::new([200.0 - d - 20.0, 150.0 + BRICK_HEIGHT + d + 20.0], [1.0, -1.0]);
+        ball.set_speed(30.0);
+
+        let r = ball.update(&Paddle::new(), &mut bricks, 1.0, false);
+
+        assert_eq!(r.bricks_hit, 1);
+        assert!(utils::len(utils::sub(ball.dir(), utils::normalize([-1.0, 1.0]))) < 0.001);
+    }
+
+    #[test]
+    fn a_moving_brick_adds_its_velocity_to_the_bounce()
+    {
+        // the brick has just moved down into the ball's way, which is heading up
+        // and right at its bottom
+        let mut bricks = bricks(&[(200.0, 150.0)], 2);
+        let moved = 5.0;
+        bricks.bricks()[0].as_mut().unwrap().moved = [0.0, moved];
+        let mut ball = Ball::new([205.0, 150.0 + BRICK_HEIGHT + BALL_SIZE + 20.0],
+            utils::normalize([1.0, -1.0]));
+        let speed = 60.0;
+        ball.set_speed(speed);
+
+        let r = ball.update(&Paddle::new(), &mut bricks, 1.0, false);
+
+        assert_eq!(r.bricks_hit, 1);
+        // a still brick would send it back down at 45 degrees, the moving one pushes
+        // it down by twice its speed on top of that
+        let a = speed / f32::sqrt(2.0);
+        let expected = utils::normalize([a, a + 2.0 * moved]);
+        assert!(utils::len(utils::sub(ball.dir(), expected)) < 0.001);
+        assert!(ball.dir()[1] > ball.dir()[0]);
+    }
+
+    #[test]
+    fn a_trapped_ball_stops_after_max_bounces()
+    {
+        // two bricks with just over a ball's width between them, and a step long
+        // enough to cross that gap many times
+        let gap = BALL_SIZE * 2.0 + 2.0;
+        let left = 100.0;
+        let right = left + BRICK_WIDTH + gap;
+        let mut bricks = bricks(&[(left, 150.0), (right, 150.0)], 100);
+        let mut ball = Ball::new([right - BALL_SIZE - 1.0, 160.0], [1.0, 0.0]);
+        ball.set_speed(1000.0);
+
+        let r = ball.update(&Paddle::new(), &mut bricks, 1.0, false);
+
+        assert_eq!(r.bricks_hit, MAX_BOUNCES as u32);
+        // the rest of the step is dropped, and the ball is still in the gap
+        assert!(ball.pos()[0] >= left + BRICK_WIDTH + BALL_SIZE);
+        assert!(ball.pos()[0] <= right - BALL_SIZE);
+    }
+
+    #[test]
+    fn a_ball_the_paddle_moved_into_is_pushed_out()
+    {
+        let paddle = Paddle::new();
+        let r = paddle.rect();
+        // just inside the left end, going straight down, so the sweep doesn't see
+        // it leaving or going further in
+        let mut ball = Ball::new([r.left() + 1.0, r.top() + r.h / 2.0], [0.0, 1.0]);
+        ball.set_speed(1.0);
+
+        ball.update(&paddle, &mut bricks(&[], 1), 0.01, false);
+
+        assert_eq!(ball.pos()[0], r.left() - BALL_SIZE);
+    }
 }
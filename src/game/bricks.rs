@@ -1,85 +1,249 @@
-use ggez::
-{
-    Context,
-    GameResult,
-    graphics::
-    {
-        self,
-        Rect,
-        Mesh,
-        Color,
-        DrawMode,
-        DrawParam,
-    },
-};
+use ggez::graphics::{Rect, Color};
+
+use std::collections::VecDeque;
 
-use crate::{BOARD_WIDTH, FOREHEAD, MAX_LEVEL};
+use crate::{BOARD_WIDTH, FOREHEAD};
+use crate::levels::Cell;
+use crate::checksum::Checksum;
+
+use super::powerup::Effect;
+use super::sim::TICKS_PER_SECOND;
+use super::utils;
 
 pub const BRICK_WIDTH: f32 = 32.36f32;
 pub const BRICK_HEIGHT: f32 = 20f32;
 
+// the most bricks that fit across the board
+pub const MAX_COLUMNS: usize = (BOARD_WIDTH / BRICK_WIDTH) as usize;
+// the most rows a level can have, so the bricks stop well above the paddle
+pub const MAX_ROWS: usize = 16;
+
+// how many steps a damaged regenerating brick takes to heal, if it isn't hit again
+const REGEN_DELAY: u32 = 5 * TICKS_PER_SECOND;
+// every brick with its middle this close to an explosion takes a hit
+pub const EXPLOSION_RADIUS: f32 = BRICK_WIDTH * 1.5;
+
+#[derive(Clone)]
 pub struct Bricks
 {
     bricks: Vec<Option<Brick>>,
-    reset: Vec<Option<Brick>>,
-    mesh: Mesh,
 }
 
 impl Bricks
 {
-    pub fn new(ctx: &mut Context, pattern: Vec<((f32, f32), u32)>) -> GameResult<Bricks>
+    pub fn new(pattern: Vec<((f32, f32), Cell)>) -> Bricks
     {
         let bricks = pattern.into_iter()
             .map(|((x, y), c)| { Some(Brick
             {
                 rect: Rect::new(x, y, BRICK_WIDTH, BRICK_HEIGHT),
-                count: c,
+                home: [x, y],
+                motion: c.motion,
+                moved: [0.0, 0.0],
+                prev: [x, y],
+                count: c.count,
+                drop: c.drop,
+                kind: c.kind,
+                max: c.count,
+                regen: 0,
+                visible: c.kind != BrickKind::Invisible,
             })})
             .collect::<Vec<_>>();
-        Ok(Bricks
+        Bricks
         {
-            bricks: bricks.clone(),
-            reset: bricks,
-            mesh: Mesh::new_rectangle(
-                ctx,
-                DrawMode::fill(),
-                Rect::new(0f32, 0f32, BRICK_WIDTH, BRICK_HEIGHT),
-                graphics::WHITE)?,
-        })
+            bricks: bricks,
+        }
     }
 
+    // the hits it takes to clear the level, steel bricks don't count
     pub fn total(&self) -> u32
     {
         self.bricks.iter()
-            .fold(0, |a, b| { a + if let Some(b) = b { b.count } else { 0 } })
+            .flatten()
+            .filter(|b| b.kind.must_clear())
+            .fold(0, |a, b| { a + b.count })
     }
 
-    pub fn bricks(&mut self) -> &mut [Option<Brick>]
+    // whether every brick that has to be broken is gone
+    pub fn cleared(&self) -> bool
     {
-        &mut self.bricks
+        !self.iter().any(|b| b.kind.must_clear())
     }
 
-    pub fn reset(&mut self)
+    pub fn begin_step(&mut self)
     {
-        self.bricks = self.reset.clone();
+        for b in self.bricks.iter_mut().flatten()
+        {
+            b.prev = b.rect.point().into();
+        }
     }
 
-    pub fn draw(&self, ctx: &mut Context) -> GameResult<()>
+    // a step of time, ticks is how many steps have been played. moving bricks go to
+    // where they are at that time, and damaged regenerating bricks heal once they
+    // haven't been hit for long enough
+    pub fn update(&mut self, ticks: u32)
     {
-        for brick in &self.bricks
+        let t = ticks as f32 / TICKS_PER_SECOND as f32;
+
+        for b in self.bricks.iter_mut().flatten()
         {
-            if let Some(brick) = brick
+            let offset = b.motion.offset(t);
+            let pos = [b.home[0] + offset[0], b.home[1] + offset[1]];
+            b.moved = utils::sub(pos, b.rect.point().into());
+            b.rect.move_to(pos);
+
+            if b.regen > 0
             {
-                graphics::draw(ctx, &self.mesh, DrawParam::new()
-                    .dest(brick.rect.point())
-                    .color(Bricks::get_color(brick.count)))?;
+                b.regen -= 1;
+                if b.regen == 0
+                {
+                    b.count = b.max;
+                }
             }
         }
+    }
 
-        Ok(())
+    // moves every brick down by dy, including where moving bricks move around. it's
+    // a jump rather than a move, so it doesn't push the balls it bounces like moving does
+    pub fn descend(&mut self, dy: f32)
+    {
+        for b in self.bricks.iter_mut().flatten()
+        {
+            b.home[1] += dy;
+            b.rect.y += dy;
+        }
     }
 
-    fn get_color(c: u32) -> Color
+    // the bottom of the lowest brick, or None if there aren't any
+    pub fn lowest(&self) -> Option<f32>
+    {
+        self.iter()
+            .map(|b| b.rect.bottom())
+            .fold(None, |a, y| Some(a.map_or(y, |a: f32| a.max(y))))
+    }
+
+    // a ball hit brick i
+    pub fn hit(&mut self, i: usize) -> Damage
+    {
+        let mut damage = Damage
+        {
+            hits: 0,
+            points: 0,
+            broken: Vec::new(),
+            explosions: Vec::new(),
+        };
+
+        let kind = match self.bricks[i]
+        {
+            Some(ref mut b) => match b.kind
+            {
+                // steel bricks just bounce the ball
+                BrickKind::Steel => return damage,
+                // the first hit only shows invisible bricks
+                BrickKind::Invisible if !b.visible =>
+                {
+                    b.visible = true;
+                    return damage
+                },
+                kind => kind,
+            },
+            None => return damage,
+        };
+
+        // an explosive brick that breaks damages every brick around it, which can set
+        // off more explosions. they go off in the order they were set off, and each one
+        // damages the bricks in order, so the same hit always does the same thing
+        let mut explosions = VecDeque::new();
+        if self.damage(i, &mut damage) && kind == BrickKind::Explosive
+        {
+            explosions.extend(damage.broken.last().map(|b| b.center()));
+        }
+
+        while let Some(center) = explosions.pop_front()
+        {
+            damage.explosions.push(center);
+
+            for j in 0..self.bricks.len()
+            {
+                let (near, explosive) = match self.bricks[j]
+                {
+                    Some(ref b) => (
+                        utils::len(utils::sub(b.center(), center)) <= EXPLOSION_RADIUS,
+                        b.kind == BrickKind::Explosive),
+                    None => (false, false),
+                };
+
+                if near && self.damage(j, &mut damage) && explosive
+                {
+                    explosions.extend(damage.broken.last().map(|b| b.center()));
+                }
+            }
+        }
+
+        damage
+    }
+
+    // takes a hit off of brick i, returns whether it broke
+    fn damage(&mut self, i: usize, damage: &mut Damage) -> bool
+    {
+        let brick = &mut self.bricks[i];
+        if let Some(b) = brick
+        {
+            if b.kind == BrickKind::Steel
+            {
+                return false
+            }
+
+            b.count -= 1;
+            b.visible = true;
+            damage.hits += 1;
+            damage.points += b.kind.points();
+
+            if b.count == 0
+            {
+                damage.broken.push(*b);
+                *brick = None;
+                return true
+            }
+            if b.kind == BrickKind::Regen
+            {
+                b.regen = REGEN_DELAY;
+            }
+        }
+
+        false
+    }
+
+    pub fn bricks(&mut self) -> &mut [Option<Brick>]
+    {
+        &mut self.bricks
+    }
+
+    // the remaining bricks, for drawing
+    pub fn iter(&self) -> impl Iterator<Item = &Brick>
+    {
+        self.bricks.iter().flatten()
+    }
+
+    // steel is always grey, explosive bricks are darker and regenerating ones lighter
+    // than the color for their hits
+    pub fn get_color(kind: BrickKind, c: u32) -> Color
+    {
+        let color = Bricks::count_color(c);
+        match kind
+        {
+            BrickKind::Steel => Color::from_rgb(128, 128, 128),
+            BrickKind::Explosive => Color::new(color.r * 0.5, color.g * 0.5, color.b * 0.5, 1.0),
+            BrickKind::Regen => Color::new(
+                (color.r + 1.0) / 2.0,
+                (color.g + 1.0) / 2.0,
+                (color.b + 1.0) / 2.0,
+                1.0),
+            BrickKind::Normal | BrickKind::Invisible => color,
+        }
+    }
+
+    fn count_color(c: u32) -> Color
     {
         match c
         {
@@ -108,7 +272,7 @@ impl Bricks
         }
     }
 
-    pub fn make_pattern(p: Vec<Vec<u32>>) -> Vec<((f32, f32), u32)>
+    pub fn make_pattern(p: Vec<Vec<Cell>>) -> Vec<((f32, f32), Cell)>
     {
         let mut pattern = Vec::new();
 
@@ -117,7 +281,7 @@ impl Bricks
             let offset = (BOARD_WIDTH - row.len() as f32 * BRICK_WIDTH) / 2f32;
             for (x, c) in row.into_iter().enumerate()
             {
-                if c > 0
+                if c.count > 0
                 {
                     pattern.push((
                         (
@@ -132,89 +296,6 @@ impl Bricks
 
         pattern
     }
-
-    pub fn get_pattern(ctx: &mut Context, level: u32) -> GameResult<Bricks>
-    {
-        match level
-        {
-            0  => Bricks::new(ctx, Bricks::make_pattern(Vec::new())),
-            1  => Bricks::new(ctx, Bricks::make_pattern(vec![
-                vec![],
-                vec![],
-                vec![1, 1, 1, 1, 1, 1, 1, 1],
-                vec![1, 1, 1, 1, 1, 1, 1, 1],
-                vec![1, 1, 1, 1, 1, 1, 1, 1],
-            ])),
-            2  => Bricks::new(ctx, Bricks::make_pattern(vec![
-                vec![],
-                vec![],
-                vec![1, 1, 2, 2, 2, 2, 1, 1],
-                vec![1, 1, 2, 3, 3, 2, 1, 1],
-                vec![1, 1, 2, 2, 2, 2, 1, 1],
-            ])),
-            3  => Bricks::new(ctx, Bricks::make_pattern(vec![
-                vec![],
-                vec![],
-                vec![2, 2, 2, 2, 2, 2, 2, 2],
-                vec![2, 3, 3, 4, 4, 3, 3, 2],
-                vec![2, 2, 2, 2, 2, 2, 2, 2],
-            ])),
-            4  => Bricks::new(ctx, Bricks::make_pattern(vec![
-                vec![],
-                vec![],
-                vec![2, 2, 3, 3, 4, 4, 5, 4, 4, 3, 3, 2, 2],
-                vec![3, 3, 2, 2, 1, 1, 0, 1, 1, 2, 2, 3, 3],
-                vec![1, 0, 1, 0, 1, 0, 0, 0, 1, 0, 1, 0, 1],
-            ])),
-            5  => Bricks::new(ctx, Bricks::make_pattern(vec![
-                vec![],
-                vec![],
-                vec![4, 4, 4, 4, 4, 4, 4, 4, 4, 4, 4, 4, 4],
-                vec![0, 0, 5, 5, 6, 6, 6, 6, 6, 5, 5, 0, 0],
-                vec![4, 5, 4, 5, 4, 5, 6, 5, 4, 5, 4, 5, 4],
-            ])),
-            6  => Bricks::new(ctx, Bricks::make_pattern(vec![
-                vec![],
-                vec![],
-                vec![1 , 3 , 6 , 9 , 12, 15, 12, 9 , 6 , 3 , 1 ],
-                vec![3 , 6 , 9 , 12, 15, 18, 15, 12, 9 , 6 , 3 ],
-                vec![10, 10, 10, 10, 10, 10, 10, 10, 10, 10, 10],
-            ])),
-            7  => Bricks::new(ctx, Bricks::make_pattern(vec![
-                vec![],
-                vec![],
-                vec![11, 12, 13, 14, 15, 16, 15, 14, 13, 12, 11],
-                vec![11, 13, 15, 17, 19, 0 , 19, 17, 15, 13, 11],
-                vec![11, 12, 13, 14, 15, 16, 15, 14, 13, 12, 11],
-            ])),
-            8  => Bricks::new(ctx, Bricks::make_pattern(vec![
-                vec![],
-                vec![],
-                vec![14, 16, 14, 0 , 0 , 15, 0 , 0 , 14, 16, 14],
-                vec![14, 16, 14, 0 , 0 , 18, 0 , 0 , 14, 16, 14],
-                vec![14, 16, 14, 0 , 0 , 18, 0 , 0 , 14, 16, 14],
-                vec![15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15],
-            ])),
-            9  => Bricks::new(ctx, Bricks::make_pattern(vec![
-                vec![],
-                vec![],
-                vec![15, 16, 17, 18, 19, 19, 19, 18, 17, 16, 15],
-                vec![17, 18, 19, 18, 17, 19, 17, 18, 19, 18, 17],
-                vec![19, 18, 17, 16, 15, 19, 15, 16, 17, 18, 19],
-                vec![12, 0 , 12, 0 , 12, 0 , 12, 0 , 12, 0 , 12],
-            ])),
-            10 => Bricks::new(ctx, Bricks::make_pattern(vec![
-                vec![],
-                vec![20, 0 , 20, 0 , 20, 0 , 20, 0 , 20, 0 , 20],
-                vec![19, 19, 19, 19, 19, 19, 19, 19, 19, 19, 19],
-                vec![20, 19, 20, 20, 20, 20, 20, 20, 20, 20, 20, 19, 20],
-                vec![19, 19, 19, 19, 19, 19, 19, 19, 19, 19, 19],
-                vec![20, 0 , 20, 0 , 20, 0 , 20, 0 , 20, 0 , 20],
-                vec![0 , 16, 0 , 16, 0 , 19, 0 , 16, 0 , 16, 0 ],
-            ])),
-            x => panic!("level outside of range! (was {}, max {})", x, MAX_LEVEL),
-        }
-    }
 }
 
 #[derive(Copy, Clone)]
@@ -222,4 +303,276 @@ pub struct Brick
 {
     pub rect: Rect,
     pub count: u32,
+    // what the brick always drops when it breaks, instead of maybe dropping something
+    pub drop: Option<Effect>,
+    pub kind: BrickKind,
+
+    // the hits it started with, regenerating bricks heal back to this
+    pub max: u32,
+    // the steps until a damaged regenerating brick heals
+    pub regen: u32,
+    // invisible bricks aren't drawn until they're hit
+    pub visible: bool,
+
+    // where it was laid out, it moves around here
+    pub home: [f32; 2],
+    pub motion: Motion,
+    // how far it moved in the last step
+    pub moved: [f32; 2],
+    // the position before the last step
+    prev: [f32; 2],
+}
+
+impl Brick
+{
+    pub fn center(&self) -> [f32; 2]
+    {
+        [self.rect.x + self.rect.w / 2.0, self.rect.y + self.rect.h / 2.0]
+    }
+
+    // the position part of the way between the last step and this one
+    pub fn lerp_pos(&self, alpha: f32) -> [f32; 2]
+    {
+        [
+            self.prev[0] + (self.rect.x - self.prev[0]) * alpha,
+            self.prev[1] + (self.rect.y - self.prev[1]) * alpha,
+        ]
+    }
+}
+
+// how a brick moves around where it was laid out
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Motion
+{
+    Still,
+    // side to side by amplitude pixels each way, taking period seconds to go there
+    // and back
+    Sway
+    {
+        amplitude: f32,
+        period: f32,
+    },
+    // round in a circle of radius pixels, taking period seconds to go round
+    Orbit
+    {
+        radius: f32,
+        period: f32,
+    },
+}
+
+impl Motion
+{
+    // how far from home it is, t seconds in
+    pub fn offset(self, t: f32) -> [f32; 2]
+    {
+        match self
+        {
+            Motion::Still => [0.0, 0.0],
+            Motion::Sway { amplitude, period } =>
+            {
+                let a = t / period * std::f32::consts::TAU;
+                [amplitude * f32::sin(a), 0.0]
+            },
+            // it starts at the top of the circle, so it starts at home
+            Motion::Orbit { radius, period } =>
+            {
+                let a = t / period * std::f32::consts::TAU;
+                [radius * f32::sin(a), radius * (1.0 - f32::cos(a))]
+            },
+        }
+    }
+
+    pub fn hash(self, c: &mut Checksum)
+    {
+        match self
+        {
+            Motion::Still => c.u32(0),
+            Motion::Sway { amplitude, period } =>
+            {
+                c.u32(1);
+                c.f32(amplitude);
+                c.f32(period);
+            },
+            Motion::Orbit { radius, period } =>
+            {
+                c.u32(2);
+                c.f32(radius);
+                c.f32(period);
+            },
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum BrickKind
+{
+    Normal,
+    // can't be broken, and doesn't need to be
+    Steel,
+    // damages the bricks next to it when it breaks
+    Explosive,
+    // heals if it isn't hit again for a while
+    Regen,
+    // isn't seen until it's hit, and that first hit doesn't count
+    Invisible,
+}
+
+impl BrickKind
+{
+    // whether it has to be broken to clear the level
+    pub fn must_clear(self) -> bool
+    {
+        self != BrickKind::Steel
+    }
+
+    // the points for each hit, before the combo
+    pub fn points(self) -> u32
+    {
+        match self
+        {
+            BrickKind::Normal => 10,
+            BrickKind::Steel => 0,
+            BrickKind::Explosive => 15,
+            BrickKind::Regen => 20,
+            BrickKind::Invisible => 25,
+        }
+    }
+
+    // a number for checksums
+    pub fn code(self) -> u32
+    {
+        match self
+        {
+            BrickKind::Normal => 0,
+            BrickKind::Steel => 1,
+            BrickKind::Explosive => 2,
+            BrickKind::Regen => 3,
+            BrickKind::Invisible => 4,
+        }
+    }
+}
+
+// what a hit did
+pub struct Damage
+{
+    // how many hits were taken off of bricks
+    pub hits: u32,
+    // the points for those hits
+    pub points: u32,
+    // the bricks that broke
+    pub broken: Vec<Brick>,
+    // where explosions went off
+    pub explosions: Vec<[f32; 2]>,
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::levels::Level;
+
+    fn bricks(rows: &str) -> Bricks
+    {
+        let level = Level::parse("test.lvl", &format!("name: Test\nauthor: Someone\n---\n{}", rows))
+            .unwrap();
+        Bricks::new(Bricks::make_pattern(level.pattern))
+    }
+
+    // which column and row each brick's middle is in
+    fn cell_of(center: [f32; 2], columns: usize) -> (usize, usize)
+    {
+        let left = (BOARD_WIDTH - columns as f32 * BRICK_WIDTH) / 2.0;
+        (((center[0] - left) / BRICK_WIDTH) as usize, ((center[1] - FOREHEAD) / BRICK_HEIGHT) as usize)
+    }
+
+    #[test]
+    fn an_explosion_sets_off_the_explosive_bricks_around_it()
+    {
+        // the explosions go left to right along the explosive bricks, past the steel
+        // brick without hurting it, and the healing brick at the end only takes one hit
+        let rows = " 1x 1x  s  0 1x  1\n 0  2r 1x 1x  0  3r";
+
+        let run = ||
+        {
+            let mut bricks = bricks(rows);
+            let damage = bricks.hit(0);
+            (bricks, damage)
+        };
+        let (bricks, damage) = run();
+
+        let mut broken = damage.broken.iter().map(|b| cell_of(b.center(), 6)).collect::<Vec<_>>();
+        broken.sort_unstable();
+        assert_eq!(broken, vec![(0, 0), (1, 0), (1, 1), (2, 1), (3, 1), (4, 0), (5, 0)]);
+        assert_eq!(damage.explosions.iter().map(|c| cell_of(*c, 6)).collect::<Vec<_>>(),
+            vec![(0, 0), (1, 0), (2, 1), (3, 1), (4, 0)]);
+        assert_eq!(damage.hits, 9);
+
+        let left = bricks.iter().collect::<Vec<_>>();
+        assert_eq!(left.len(), 2);
+        assert_eq!((left[0].kind, left[0].count), (BrickKind::Steel, 1));
+        assert_eq!((left[1].kind, left[1].count, left[1].regen), (BrickKind::Regen, 2, REGEN_DELAY));
+
+        // and it goes off the same way every time
+        let (again, damage_again) = run();
+        assert_eq!(damage_again.broken.iter().map(|b| b.center()).collect::<Vec<_>>(),
+            damage.broken.iter().map(|b| b.center()).collect::<Vec<_>>());
+        assert_eq!(damage_again.explosions, damage.explosions);
+        assert_eq!(damage_again.points, damage.points);
+        assert_eq!(again.iter().map(|b| (b.center(), b.count)).collect::<Vec<_>>(),
+            bricks.iter().map(|b| (b.center(), b.count)).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn descending_doesnt_count_as_moving()
+    {
+        let cell = Cell
+        {
+            count: 1,
+            drop: None,
+            kind: BrickKind::Normal,
+            motion: Motion::Still,
+        };
+        let mut bricks = Bricks::new(vec![((40.0, 60.0), cell)]);
+
+        bricks.begin_step();
+        bricks.update(1);
+        bricks.descend(BRICK_HEIGHT);
+
+        let b = bricks.iter().next().unwrap();
+        assert_eq!(b.rect.y, 60.0 + BRICK_HEIGHT);
+        assert_eq!(b.home, [40.0, 60.0 + BRICK_HEIGHT]);
+        assert_eq!(b.moved, [0.0, 0.0]);
+    }
+
+    #[test]
+    fn a_swaying_brick_is_back_home_after_one_period()
+    {
+        let cell = Cell
+        {
+            count: 1,
+            drop: None,
+            kind: BrickKind::Normal,
+            motion: Motion::Sway
+            {
+                amplitude: 20.0,
+                period: 2.0,
+            },
+        };
+        let mut bricks = Bricks::new(vec![((40.0, 60.0), cell)]);
+
+        let mut furthest: f32 = 0.0;
+        for tick in 1..=2 * TICKS_PER_SECOND
+        {
+            bricks.begin_step();
+            bricks.update(tick);
+
+            let b = bricks.iter().next().unwrap();
+            assert_eq!(b.rect.y, 60.0);
+            furthest = furthest.max((b.rect.x - 40.0).abs());
+        }
+
+        let b = bricks.iter().next().unwrap();
+        assert!((b.rect.x - 40.0).abs() < 0.001);
+        assert!((furthest - 20.0).abs() < 0.001);
+    }
 }
@@ -7,17 +7,26 @@ use ggez::
         self,
         Rect,
         Mesh,
+        Color,
         DrawMode,
         DrawParam,
     },
 };
 
-
 use crate::{BOARD_WIDTH, FOREHEAD};
 use crate::ui::TextRect;
 
+use super::powerup::Effect;
+use super::sim::TICKS_PER_SECOND;
+
 const BORDER: f32 = 5.0;
 const SEP: f32 = 1.5;
+// the effect icons are this much wider than they are tall
+const ICON_RATIO: f32 = 1.6;
+// in time attack the time left flashes once it's this many seconds or less, switching
+// every FLASH_STEPS steps
+const LOW_TIME: u32 = 10;
+const FLASH_STEPS: u32 = TICKS_PER_SECOND / 4;
 
 pub struct ForeHead
 {
@@ -32,15 +41,23 @@ pub struct ForeHead
     ball: Mesh,
 
     level_text: TextRect,
+
+    // the effects that are going, under the level name
+    icon: Mesh,
+    icon_size: f32,
+    effects: Vec<EffectIcon>,
     
+    score: u32,
+    multiplier: u32,
+    timer: u32,
     lives: u32,
     starting_lives: u32,
-    max_score: u32,
 }
 
 impl ForeHead
 {
-    pub fn new(ctx: &mut Context, level: u32, max_score: u32, starting_lives: u32)
+    // there's room for max_lives lives, though it starts with starting_lives
+    pub fn new(ctx: &mut Context, name: &str, starting_lives: u32, max_lives: u32)
         -> GameResult<ForeHead>
     {
         let size = (FOREHEAD - BORDER * 2.0 - SEP) / 2.0;
@@ -56,7 +73,7 @@ impl ForeHead
             ctx,
             score_text.rect().right() + SEP,
             BORDER,
-            format!("000/{:03}", max_score),
+            "000000 x1",
             None,
             size,
             Some(graphics::WHITE));
@@ -78,13 +95,13 @@ impl ForeHead
             Some(graphics::WHITE));
         
         let ball_r =
-            ((FOREHEAD - BORDER * 2.0 - (starting_lives as f32- 1.0) * SEP) / starting_lives as f32)
+            ((FOREHEAD - BORDER * 2.0 - (max_lives as f32 - 1.0) * SEP) / max_lives as f32)
             / 2.0;
 
         let level_text = TextRect::new(
             ctx,
             0.0, BORDER,
-            format!("Level {}", level),
+            name,
             None,
             size,
             Some(graphics::WHITE))
@@ -101,7 +118,7 @@ impl ForeHead
             score_val: score_val,
             timer_text: timer_text,
             timer_val: timer_val,
-            ball_r,
+            ball_r: ball_r,
             ball: Mesh::new_circle(
                 ctx,
                 DrawMode::fill(),
@@ -110,20 +127,62 @@ impl ForeHead
                 0.01,
                 graphics::WHITE)?,
             level_text: level_text,
+            // white so it can be drawn in the effect's color
+            icon: Mesh::new_rectangle(
+                ctx,
+                DrawMode::fill(),
+                Rect::new(0.0, 0.0, size * ICON_RATIO, size),
+                graphics::WHITE)?,
+            icon_size: size,
+            effects: Vec::new(),
+            score: 0,
+            multiplier: 1,
+            timer: 0,
             lives: starting_lives,
             starting_lives: starting_lives,
-            max_score: max_score,
         })
     }
 
-    pub fn set_score(&mut self, ctx: &mut Context, score: u32)
+    // the points with the combo multiplier next to them, the text is only remade if
+    // either changed
+    pub fn set_score(&mut self, ctx: &mut Context, score: u32, multiplier: u32)
     {
-        self.score_val.change_text(ctx, format!("{:03}/{:03}", score, self.max_score));
+        if score != self.score || multiplier != self.multiplier
+        {
+            self.score = score;
+            self.multiplier = multiplier;
+            self.score_val.change_text(ctx, format!("{:06} x{}", score, multiplier));
+        }
     }
 
     pub fn set_timer(&mut self, ctx: &mut Context, timer: u32)
     {
-        self.timer_val.change_text(ctx, format!("{:04}", timer));
+        if timer != self.timer
+        {
+            self.timer = timer;
+            self.timer_val.change_text(ctx, format!("{:04}", timer));
+        }
+    }
+
+    // in time attack the timer shows the time left instead, from the steps left
+    pub fn set_time_left(&mut self, ctx: &mut Context, left: u32)
+    {
+        let secs = left.div_ceil(TICKS_PER_SECOND);
+        if secs != self.timer
+        {
+            self.timer = secs;
+            self.timer_val.change_text(ctx, format!("{:04}", secs));
+        }
+
+        let flash = secs <= LOW_TIME && (left / FLASH_STEPS) % 2 == 0;
+        self.timer_val.set_color(if flash
+        {
+            Color::from_rgb(255, 64, 64)
+        }
+        else
+        {
+            graphics::WHITE
+        });
     }
 
     pub fn set_lives(&mut self, lives: u32)
@@ -131,11 +190,71 @@ impl ForeHead
         self.lives = lives;
     }
 
+    // effects are (effect, steps left), the icons are only remade if the effects
+    // changed, and the countdowns if the seconds left changed
+    pub fn set_effects(&mut self, ctx: &mut Context, effects: &[(Effect, u32)])
+    {
+        let same = self.effects.len() == effects.len()
+            && self.effects.iter().zip(effects).all(|(i, (e, _))| i.effect == *e);
+
+        if !same
+        {
+            self.effects = effects.iter()
+                .map(|(e, left)| EffectIcon::new(ctx, *e, left.div_ceil(TICKS_PER_SECOND),
+                    self.icon_size))
+                .collect();
+        }
+        else
+        {
+            for (icon, (_, left)) in self.effects.iter_mut().zip(effects)
+            {
+                let secs = left.div_ceil(TICKS_PER_SECOND);
+                if secs != icon.secs
+                {
+                    icon.secs = secs;
+                    icon.time.change_text(ctx, secs);
+                }
+            }
+        }
+
+        // the icons are centered under the level name
+        let icon_w = self.icon_size * ICON_RATIO;
+        let total = self.effects.iter()
+            .map(|i| icon_w + SEP * 2.0 + i.time.rect().w)
+            .sum::<f32>()
+            + self.effects.len().saturating_sub(1) as f32 * BORDER * 2.0;
+
+        let y = self.level_text.rect().bottom() + SEP;
+        let mut x = BOARD_WIDTH / 2.0 - total / 2.0;
+        for icon in self.effects.iter_mut()
+        {
+            icon.x = x;
+            let letter = icon.letter.rect();
+            icon.letter.move_to(x + (icon_w - letter.w) / 2.0, y + (self.icon_size - letter.h) / 2.0);
+            icon.time.move_to(x + icon_w + SEP * 2.0, y);
+
+            x += icon_w + SEP * 2.0 + icon.time.rect().w + BORDER * 2.0;
+        }
+    }
+
+    // for a new wave in endless mode, the score carries on
+    pub fn set_level(&mut self, ctx: &mut Context, name: &str)
+    {
+        self.level_text.change_text(ctx, name);
+        let level = self.level_text.rect();
+        self.level_text.move_to(BOARD_WIDTH / 2.0 - level.w / 2.0, level.y);
+    }
+
     pub fn reset(&mut self, ctx: &mut Context)
     {
-        self.score_val.change_text(ctx, format!("000/{:03}", self.max_score));
+        self.score_val.change_text(ctx, "000000 x1");
         self.timer_val.change_text(ctx, "0000");
+        self.timer_val.set_color(graphics::WHITE);
+        self.score = 0;
+        self.multiplier = 1;
+        self.timer = 0;
         self.lives = self.starting_lives;
+        self.effects.clear();
     }
 
     pub fn draw(&self, ctx: &mut Context) -> GameResult<()>
@@ -150,6 +269,15 @@ impl ForeHead
 
         self.level_text.draw(ctx)?;
 
+        for icon in &self.effects
+        {
+            graphics::draw(ctx, &self.icon, DrawParam::new()
+                .dest([icon.x, icon.time.rect().y])
+                .color(icon.effect.color()))?;
+            icon.letter.draw(ctx)?;
+            icon.time.draw(ctx)?;
+        }
+
         for i in 0..self.lives
         {
             graphics::draw(ctx, &self.ball, DrawParam::new()
@@ -162,3 +290,31 @@ impl ForeHead
         Ok(())
     }
 }
+
+// an effect that's going, with the seconds it has left
+struct EffectIcon
+{
+    effect: Effect,
+    secs: u32,
+
+    x: f32,
+    letter: TextRect,
+    time: TextRect,
+}
+
+impl EffectIcon
+{
+    // it's put in place by set_effects
+    fn new(ctx: &mut Context, effect: Effect, secs: u32, size: f32) -> EffectIcon
+    {
+        EffectIcon
+        {
+            effect: effect,
+            secs: secs,
+
+            x: 0.0,
+            letter: TextRect::new(ctx, 0.0, 0.0, effect.letter(), None, size * 0.8, None),
+            time: TextRect::new(ctx, 0.0, 0.0, secs, None, size, Some(graphics::WHITE)),
+        }
+    }
+}
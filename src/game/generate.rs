@@ -0,0 +1,374 @@
+use crate::rng::Rng;
+use crate::levels::Cell;
+
+use super::bricks::{BrickKind, Motion};
+use super::powerup::Drops;
+use super::sim::Rules;
+
+// how many bricks are in each row, it's odd so the rows have a middle
+const COLUMNS: usize = 13;
+// the empty rows above the bricks
+const TOP_ROWS: usize = 2;
+const MAX_ROWS: u32 = 10;
+const MAX_COUNT: u32 = 9;
+// the chance that a brick is left out, in the styles that leave gaps at random
+const GAP_CHANCE: f32 = 0.25;
+// the ball gets this much faster every wave, up to MAX_SPEED times its normal speed
+const SPEED_STEP: f32 = 0.05;
+const MAX_SPEED: f32 = 1.6;
+// how hard the daily challenge is
+const DAILY_DIFFICULTY: u32 = 6;
+// mixed into the day, so the daily challenge isn't the same as an endless wave
+const DAILY_SEED: u64 = 0x6461_696c_7900_0000;
+
+// the shapes the generator makes
+#[derive(Copy, Clone)]
+enum Style
+{
+    // rows of random bricks, the same on both sides
+    Mirrored,
+    // toughest in the middle, sometimes hollow
+    Diamond,
+    // two kinds of brick, alternating
+    Checkerboard,
+    // tougher towards the top or the bottom
+    Gradient,
+}
+
+const STYLES: [Style; 4] = [Style::Mirrored, Style::Diamond, Style::Checkerboard, Style::Gradient];
+
+// a generated level, one wave of endless mode or a daily challenge
+pub struct Wave
+{
+    pub pattern: Vec<Vec<Cell>>,
+    pub rules: Rules,
+}
+
+// the bricks for a wave of endless mode, waves count from 1 and each is generated
+// with its number as the difficulty, with a faster ball as it goes
+pub fn wave(seed: u32, wave: u32) -> Wave
+{
+    Wave
+    {
+        pattern: pattern(((seed as u64) << 32) | wave as u64, wave),
+        rules: Rules
+        {
+            balls: 1,
+            drops: Drops::default(),
+            creep: None,
+            time_limit: None,
+            par: None,
+            speed: f32::min(1.0 + wave.saturating_sub(1) as f32 * SPEED_STEP, MAX_SPEED),
+        },
+    }
+}
+
+// the daily challenge for a day from daily::today(), the same for everyone
+pub fn daily(day: u32) -> Wave
+{
+    Wave
+    {
+        pattern: pattern(DAILY_SEED | day as u64, DAILY_DIFFICULTY),
+        rules: Rules
+        {
+            balls: 1,
+            drops: Drops::default(),
+            creep: None,
+            time_limit: None,
+            par: None,
+            speed: 1.0,
+        },
+    }
+}
+
+// a pattern of bricks in the same shape as a level's, always the same for the same
+// seed and difficulty. difficulty starts at 1, and the higher it is the more rows
+// there are, the more hits the bricks take and the more special bricks there are.
+// it uses the game's own Rng, whose chances are exact 24 bit fractions of an f32, so
+// it's the same on every platform
+pub fn pattern(seed: u64, difficulty: u32) -> Vec<Vec<Cell>>
+{
+    let mut rng = Rng::new(seed);
+    let difficulty = u32::max(difficulty, 1);
+
+    let rows = u32::min(2 + difficulty, MAX_ROWS) as usize;
+    let max_count = u32::min(1 + difficulty / 2, MAX_COUNT);
+    // special bricks start showing up at difficulty 3
+    let special_chance = f32::min(difficulty.saturating_sub(2) as f32 * 0.04, 0.3);
+
+    // only the left half and the middle column are made, the right half mirrors them
+    let half = COLUMNS / 2 + 1;
+    let mut counts = vec![vec![0; half]; rows];
+
+    match STYLES[rng.below(STYLES.len() as u32) as usize]
+    {
+        Style::Mirrored =>
+        {
+            for c in counts.iter_mut().flatten()
+            {
+                if !rng.chance(GAP_CHANCE)
+                {
+                    *c = 1 + rng.below(max_count);
+                }
+            }
+        },
+        Style::Diamond =>
+        {
+            // the distance from the middle goes from 0 there to den at the edge of the
+            // diamond, rows are measured at double size so an even number has a middle
+            let den = (half as u32 - 1) * (rows as u32 - 1);
+            let hollow = rng.chance(0.5);
+            for (y, row) in counts.iter_mut().enumerate()
+            {
+                for (x, c) in row.iter_mut().enumerate()
+                {
+                    let dx = (half - 1 - x) as u32;
+                    let dy = (2 * y as i32 - (rows as i32 - 1)).unsigned_abs();
+                    let dist = dx * (rows as u32 - 1) + dy * (half as u32 - 1);
+
+                    if dist <= den && !(hollow && dist * 2 < den)
+                    {
+                        *c = 1 + (max_count - 1) * (den - dist) / den;
+                    }
+                }
+            }
+        },
+        Style::Checkerboard =>
+        {
+            // the second kind can be gaps
+            let a = 1 + rng.below(max_count);
+            let b = rng.below(max_count + 1);
+            for (y, row) in counts.iter_mut().enumerate()
+            {
+                for (x, c) in row.iter_mut().enumerate()
+                {
+                    *c = if (x + y) % 2 == 0 { a } else { b };
+                }
+            }
+        },
+        Style::Gradient =>
+        {
+            let top = rng.chance(0.5);
+            // whole columns can be left out
+            let gaps = (0..half).map(|_| rng.chance(GAP_CHANCE / 2.0)).collect::<Vec<_>>();
+            for (y, row) in counts.iter_mut().enumerate()
+            {
+                let y = if top { y } else { rows - 1 - y };
+                let count = u32::max(1, max_count - y as u32 * max_count / rows as u32);
+                for (x, c) in row.iter_mut().enumerate()
+                {
+                    if !gaps[x]
+                    {
+                        *c = count;
+                    }
+                }
+            }
+        },
+    }
+
+    // every pattern needs something to break
+    if counts.iter().flatten().all(|c| *c == 0)
+    {
+        counts[0][half - 1] = 1;
+    }
+
+    let mut pattern = vec![Vec::new(); TOP_ROWS];
+    for row in counts
+    {
+        let mut cells = vec![empty(); COLUMNS];
+        for (x, count) in row.into_iter().enumerate()
+        {
+            if count == 0
+            {
+                continue;
+            }
+
+            let kind = if rng.chance(special_chance)
+            {
+                [BrickKind::Explosive, BrickKind::Regen, BrickKind::Invisible]
+                    [rng.below(3) as usize]
+            }
+            else
+            {
+                BrickKind::Normal
+            };
+
+            let cell = Cell
+            {
+                count: count,
+                drop: None,
+                kind: kind,
+                motion: Motion::Still,
+            };
+            cells[x] = cell;
+            cells[COLUMNS - 1 - x] = cell;
+        }
+        pattern.push(cells);
+    }
+
+    pattern
+}
+
+fn empty() -> Cell
+{
+    Cell
+    {
+        count: 0,
+        drop: None,
+        kind: BrickKind::Normal,
+        motion: Motion::Still,
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::checksum::Checksum;
+
+    // the pattern written out like a level file's rows
+    fn layout(pattern: &[Vec<Cell>]) -> Vec<String>
+    {
+        pattern.iter()
+            .map(|row| if row.is_empty()
+            {
+                ".".to_string()
+            }
+            else
+            {
+                row.iter()
+                    .map(|c| match c.kind
+                    {
+                        BrickKind::Explosive => format!("{}x", c.count),
+                        BrickKind::Regen => format!("{}r", c.count),
+                        BrickKind::Invisible => format!("{}i", c.count),
+                        _ => format!("{} ", c.count),
+                    })
+                    .collect::<Vec<_>>()
+                    .join(" ")
+                    .trim_end()
+                    .to_string()
+            })
+            .collect()
+    }
+
+    fn hash(pattern: &[Vec<Cell>]) -> u64
+    {
+        let mut c = Checksum::new();
+        for row in pattern
+        {
+            c.u32(row.len() as u32);
+            for cell in row
+            {
+                c.u32(cell.count);
+                c.u32(cell.kind.code());
+            }
+        }
+        c.finish()
+    }
+
+    // the first number from the seed picks the style, these are one of each
+    const MIRRORED: u64 = 3;
+    const DIAMOND: u64 = 4;
+    const CHECKERBOARD: u64 = 9;
+    const GRADIENT: u64 = 0;
+
+    #[test]
+    fn the_seeds_are_one_of_each_style()
+    {
+        let style = |seed| Rng::new(seed).below(STYLES.len() as u32);
+        assert_eq!(
+            [style(MIRRORED), style(DIAMOND), style(CHECKERBOARD), style(GRADIENT)],
+            [0, 1, 2, 3]);
+    }
+
+    #[test]
+    fn mirrored_layout_stays_the_same()
+    {
+        assert_eq!(layout(&pattern(MIRRORED, 4)), vec![
+            ".",
+            ".",
+            "2  0  0  1  2  3r 2  3r 2  1  0  0  2",
+            "3  1  0  0  3  3  0  3  3  0  0  1  3",
+            "3  0  0  3  3  0  2  0  3  3  0  0  3",
+            "0  3i 2  0  3  1  3  1  3  0  2  3i 0",
+            "0  1  3  2i 2  0  2  0  2  2i 3  1  0",
+            "2  0  2  2  3i 2  1  2  3i 2  2  0  2",
+        ]);
+    }
+
+    #[test]
+    fn diamond_layout_stays_the_same()
+    {
+        assert_eq!(layout(&pattern(DIAMOND, 4)), vec![
+            ".",
+            ".",
+            "0  0  0  0  0  0  1  0  0  0  0  0  0",
+            "0  0  0  0  1  1  1  1  1  0  0  0  0",
+            "0  0  1  1  1  2  2  2  1  1  1  0  0",
+            "0  0  1  1  1  2  2  2  1  1  1  0  0",
+            "0  0  0  0  1  1  1  1  1  0  0  0  0",
+            "0  0  0  0  0  0  1  0  0  0  0  0  0",
+        ]);
+    }
+
+    #[test]
+    fn checkerboard_layout_stays_the_same()
+    {
+        assert_eq!(layout(&pattern(CHECKERBOARD, 4)), vec![
+            ".",
+            ".",
+            "3  1  3  1  3  1  3  1  3  1  3  1  3",
+            "1  3  1  3  1  3  1  3  1  3  1  3  1",
+            "3  1  3  1  3  1  3  1  3  1  3  1  3",
+            "1  3  1  3  1  3  1  3  1  3  1  3  1",
+            "3x 1  3  1  3  1  3i 1  3  1  3  1  3x",
+            "1  3  1  3i 1  3  1  3  1  3i 1  3  1",
+        ]);
+    }
+
+    #[test]
+    fn gradient_layout_stays_the_same()
+    {
+        assert_eq!(layout(&pattern(GRADIENT, 4)), vec![
+            ".",
+            ".",
+            "0  3  0  3  3  3  3  3  3  3  0  3  0",
+            "0  3  0  3  3  3  3  3  3  3  0  3  0",
+            "0  2  0  2  2  2  2  2  2  2  0  2  0",
+            "0  2  0  2  2  2  2  2  2  2  0  2  0",
+            "0  1  0  1  1  1x 1  1x 1  1  0  1  0",
+            "0  1  0  1  1  1  1  1  1  1  0  1  0",
+        ]);
+    }
+
+    #[test]
+    fn harder_patterns_stay_the_same()
+    {
+        let hashes = [MIRRORED, DIAMOND, CHECKERBOARD, GRADIENT].iter()
+            .flat_map(|seed| [9, 20].iter().map(move |d| hash(&pattern(*seed, *d))))
+            .collect::<Vec<_>>();
+        // each seed at difficulty 9 then 20
+        assert_eq!(hashes, vec![
+            0xdc46_359f_d5b6_4890, 0xd58c_0839_59d2_8f8c,
+            0x151e_e659_2a7f_e185, 0x77d4_8710_e735_5765,
+            0xe62e_a53a_2e0b_e274, 0xbc0c_cf6b_4aed_27b3,
+            0x42fa_d666_0ed5_7d80, 0x0d7e_92c5_f3bf_49e8,
+        ]);
+    }
+
+    #[test]
+    fn waves_and_daily_challenges_stay_the_same()
+    {
+        let waves = [(1, 1), (1, 2), (0xdead_beef, 7)].iter()
+            .map(|(seed, w)| hash(&wave(*seed, *w).pattern))
+            .collect::<Vec<_>>();
+        let days = [0, 20_000, 20_744].iter()
+            .map(|day| hash(&daily(*day).pattern))
+            .collect::<Vec<_>>();
+        assert_eq!(waves, vec![0x6754_b5cc_9611_ffe8, 0x242b_ab7d_c31b_0065,
+            0x8bae_ace5_61ed_9f6f]);
+        assert_eq!(days, vec![0x57c9_df98_b0d4_51e6, 0x6d4b_21a2_6a23_6407,
+            0xd25b_5083_d439_fa67]);
+    }
+}
@@ -2,6 +2,8 @@ use ggez::
 {
     Context,
     GameResult,
+    GameError,
+    event::{Axis, Button as PadButton},
     input::
     {
         keyboard::KeyCode,
@@ -9,405 +11,717 @@ use ggez::
     },
 };
 
-use std::time::{Instant, Duration};
-
 use crate::
 {
     state::State,
     menu::Menu,
+    shared::Shared,
+    replay::{Replay, Source, Recorder, Playback},
+    scores::{Table, Modes, Entry},
     level_clear::LevelClear,
     level_lose::LevelLose,
+    replay_select::ReplaySelect,
+    daily::{self, Daily},
+    rng::Rng,
+    input::{Action, Input, MouseControl},
+    levels::Cell,
 };
 
 mod utils;
 mod paddle;
 mod ball;
 mod bricks;
+mod sim;
+mod render;
 mod pause_ui;
 mod forehead;
+mod powerup;
+mod generate;
+mod timestep;
+
+pub use powerup::{Effect, Drops};
+pub use bricks::{BrickKind, Motion, MAX_COLUMNS, MAX_ROWS};
+pub use sim::{Creep, Score, TICKS_PER_SECOND};
 
-use paddle::Paddle;
-use ball::Ball;
 use bricks::Bricks;
+use sim::{Sim, Rules, Phase, Outcome, InputData, Pointer};
+use render::Renderer;
 use pause_ui::PauseUI;
 use forehead::ForeHead;
+use timestep::Timestep;
 
 const LINE_LENGTH: f32 = 30.0;
-const ANGLE_CHANGE: f32 = 0.04;
 
-const STARTING_LIVES: u32 = 3;
+// what's being played
+#[derive(Copy, Clone)]
+pub enum Mode
+{
+    // a level from the levels directory, by its number
+    Level(u32),
+    // the waves of endless mode made from a seed
+    Endless(u32),
+    // the daily challenge, by its day from daily::today()
+    Daily(u32),
+}
+
+impl std::fmt::Display for Mode
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result
+    {
+        match self
+        {
+            Mode::Level(level) => write!(f, "level {}", level),
+            Mode::Endless(seed) => write!(f, "seed {:08x}", seed),
+            Mode::Daily(day) => write!(f, "daily {}", daily::date(*day)),
+        }
+    }
+}
+
+// the bricks and rules for one level or wave
+struct Stage
+{
+    name: String,
+    pattern: Vec<Vec<Cell>>,
+    rules: Rules,
+}
 
 pub struct Game
 {
-    paddle: Paddle,
-    ball: Option<Ball>,
-    bricks: Bricks,
+    sim: Sim,
+    renderer: Renderer,
     pause_ui: PauseUI,
     forehead: ForeHead,
 
-    phase: Phase,
+    mode: Mode,
+    // the wave of endless mode being played, from 1
+    wave: u32,
+    paused: bool,
+
+    // the keys and buttons, the settings say what they do
+    input: Input,
+    // where the mouse is, for mouse control
+    mouse_pos: Option<[f32; 2]>,
 
-    game_data: GameData,
-    input_data: InputData,
+    // the simulation's steps, at a fixed rate
+    timestep: Timestep,
+
+    // every run is recorded, unless it's a replay being played back
+    recorder: Recorder,
+    playback: Option<Playback>,
 }
 
 impl Game
 {
-    pub fn new(ctx: &mut Context, level: u32) -> GameResult<Game>
+    pub fn new(ctx: &mut Context, shared: &Shared, mode: Mode) -> GameResult<Game>
     {
-        let bricks = Bricks::get_pattern(ctx, level)?;
+        Game::build(ctx, shared, mode, None)
+    }
 
-        let max_score = bricks.total();
+    // plays back a replay instead of taking input
+    pub fn replay(ctx: &mut Context, shared: &Shared, replay: Replay) -> GameResult<Game>
+    {
+        let mode = match replay.source
+        {
+            Source::Level(ref id, hash) =>
+            {
+                let level = match shared.levels.find(id)
+                {
+                    Some(level) => level,
+                    None => return Err(GameError::ResourceLoadError(
+                        format!("the replay's level '{}' doesn't exist", id))),
+                };
+
+                if shared.levels.get(level).map(|l| l.checksum()) != Some(hash)
+                {
+                    return Err(GameError::ResourceLoadError(
+                        format!("level '{}' has changed since the replay was recorded", id)))
+                }
+
+                Mode::Level(level)
+            },
+            Source::Endless(seed) => Mode::Endless(seed),
+            Source::Daily(day) => Mode::Daily(day),
+        };
+
+        Game::build(ctx, shared, mode, Some(Playback::new(replay)))
+    }
+
+    fn build(ctx: &mut Context, shared: &Shared, mode: Mode, playback: Option<Playback>)
+        -> GameResult<Game>
+    {
+        let (sim, name) = Game::start(shared, &mode, &playback)?;
+
+        // the daily challenge only gets one attempt, but its replay can be watched again
+        let mut pause_ui = PauseUI::new(ctx)?;
+        if let (Mode::Daily(_), None) = (mode, &playback)
+        {
+            pause_ui.set_restart_locked(true);
+        }
 
         Ok(Game
         {
-            paddle: Paddle::new(ctx)?,
-            ball: None,
-            bricks: bricks,
-            pause_ui: PauseUI::new(ctx)?,
-            forehead: ForeHead::new(ctx, level, max_score, STARTING_LIVES)?,
+            renderer: Renderer::new(ctx)?,
+            pause_ui: pause_ui,
+            forehead: ForeHead::new(ctx, &name, sim::STARTING_LIVES, sim::MAX_LIVES)?,
+            recorder: Recorder::new(sim.seed(), sim.creep(), sim.time_limit()),
+            sim: sim,
 
-            phase: Phase::Shoot(utils::normalize([0.0, -1.0])),
+            mode: mode,
+            wave: 1,
+            paused: false,
 
-            game_data: GameData
-            {
-                score: 0,
-                max_score: max_score,
-                timer: 0,
-                level: level,
-                
-                lives: STARTING_LIVES,
-                paddle_speed: 3.5f32,
-                pause_transition: false,
-
-                start_inst: None,
-                pause_inst: None,
-                pause_dur: Duration::new(0, 0),
-            },
-            input_data: InputData
-            {
-                left_down: false,
-                right_down: false,
-                enter_down: false,
-                p_down: false,
-            },
+            input: Input::default(),
+            // where the mouse was left, until it moves
+            mouse_pos: Some(ggez::input::mouse::position(ctx).into()),
+
+            timestep: Timestep::default(),
+
+            playback: playback,
         })
     }
 
-    pub fn update(&mut self, ctx: &mut Context) -> GameResult<Option<State>>
+    // the simulation at the start of a run, and the name to show for it
+    fn start(shared: &Shared, mode: &Mode, playback: &Option<Playback>) -> GameResult<(Sim, String)>
     {
-        match &mut self.phase
+        let stage = Game::stage(shared, mode, 1)?;
+
+        // a replay creeps the way it was recorded, and the daily challenge is the
+        // same for everyone, otherwise creep mode gives every level that doesn't have
+        // its own creep the default. the same goes for time attack, which gives levels
+        // their time limit
+        let mut rules = stage.rules;
+        match playback
         {
-            // the game starts in this phase, and returns to it if the ball is destroyed
-            Phase::Shoot(ref mut angle) =>
+            Some(ref playback) =>
+            {
+                rules.creep = playback.replay().creep;
+                rules.time_limit = playback.replay().time_limit;
+            },
+            None if matches!(mode, Mode::Daily(_)) => { },
+            None =>
             {
-                // rotate the angle of the ball shooter
-                if self.input_data.left_down
+                if shared.settings.creep_mode()
                 {
-                    *angle = utils::rotate(*angle, -ANGLE_CHANGE);
+                    rules.creep = rules.creep.or(Some(Creep::DEFAULT));
                 }
-                if self.input_data.right_down
+                if !shared.settings.time_attack_mode()
                 {
-                    *angle = utils::rotate(*angle, ANGLE_CHANGE);
+                    rules.time_limit = None;
                 }
+            },
+        }
 
-                // confine the angle to between [-1.0, -0.15] to [1.0, -0.15]
-                // (y = 0 is the top of the window)
-                if utils::angle_between(*angle, [1.0, 0.0]) <
-                    utils::angle_between([1.0, -0.15], [1.0, 0.0])
-                {
-                    *angle = [1.0, -0.15];
-                }
-                if utils::angle_between(*angle, [-1.0, 0.0]) <
-                    utils::angle_between([-1.0, -0.15], [-1.0, 0.0])
-                {
-                    *angle = [-1.0, -0.15]
-                }
-                
-                // shoot the ball
-                if self.input_data.enter_down
-                {
-                    // the ball has the angle of the current shooter angle,
-                    // and starts right above the center of the paddle
-                    self.ball = Some(Ball::new(
-                        ctx,
-                        [
-                            self.paddle.rect().x + self.paddle.rect().w / 2.0,
-                            self.paddle.rect().y + ball::BALL_SIZE
-                        ],
-                        *angle)?);
-                    
-                    // if the pause_inst is Some, then we have returned to this phase
-                    // after the ball was destroyed, so we need to update the pause
-                    // duration to keep the timer accurate
-                    if let Some(pause_inst) = self.game_data.pause_inst
-                    {
-                        self.game_data.pause_dur += Instant::now()
-                            .duration_since(pause_inst);
-                        // also unset the last pause instance
-                        self.game_data.pause_inst = None;
-                    }
-                    
-                    // if the start_inst is none, then this is the first time we've been in this
-                    // phase, so this marks the start of the timer
-                    // (this should not be true if pause_inst is Some)
-                    if let None = self.game_data.start_inst
-                    {
-                        self.game_data.start_inst = Some(Instant::now());
-                    }
+        let sim = Sim::new(
+            Bricks::new(Bricks::make_pattern(stage.pattern)),
+            rules,
+            Game::seed(playback));
 
-                    // move to the next phase
-                    self.phase = Phase::Bounce;
-                }
-            }
-            // this is the main gameplay phase
-            Phase::Bounce =>
+        Ok((sim, stage.name))
+    }
+
+    // the bricks and rules for a level, a wave of endless mode or a daily challenge
+    fn stage(shared: &Shared, mode: &Mode, wave: u32) -> GameResult<Stage>
+    {
+        match *mode
+        {
+            Mode::Level(level) =>
             {
-                // update the timer
-                if let Some(start_inst) = self.game_data.start_inst
-                {
-                    // the current game duration, minus the time spent paused
-                    let game_dur = Instant::now()
-                        .duration_since(start_inst) - self.game_data.pause_dur;
-                    
-                    // the timer is in seconds only, so only update it if at least one
-                    // more second has passed
-                    if  game_dur.as_secs() as u32 > self.game_data.timer
-                    {
-                        self.game_data.timer = game_dur.as_secs() as u32;
-                        self.forehead.set_timer(ctx, self.game_data.timer);
-                    }
-                }
-                
-                // move the paddle, the paddle will handle confining itself to the board
-                if self.input_data.left_down
-                {
-                    self.paddle.shift(-self.game_data.paddle_speed);
-                }
-                if self.input_data.right_down
+                let level_data = match shared.levels.get(level)
                 {
-                    self.paddle.shift(self.game_data.paddle_speed);
-                }
-                
-                // this should always be true in the bounce phase
-                // (maybe .expect() it?)
-                if let Some(ref mut ball) = &mut self.ball
+                    Some(l) => l,
+                    None => return Err(GameError::ResourceLoadError(
+                        format!("there is no level {}", level))),
+                };
+
+                Ok(Stage
                 {
-                    // the ball update handles all bouncing, including going off the board
-                    // and being destroyed, and bouncing off of / breaking bricks
-                    let r = ball.update(&self.paddle, &mut self.bricks);
-                    if r.destroyed_ball
-                    {
-                        // lose a life and update the forehead
-                        self.game_data.lives -= 1;
-                        self.forehead.set_lives(self.game_data.lives);
-                        if self.game_data.lives == 0
-                        {
-                            return Ok(Some(State::LevelLose(LevelLose::new(
-                                ctx,
-                                self.game_data.timer,
-                                self.game_data.score,
-                                self.game_data.max_score,
-                                self.game_data.level)?)))
-                        }
-                        // pause the timer
-                        self.game_data.pause_inst = Some(Instant::now());
-                        // get rid of the ball so it isn't drawn
-                        self.ball = None;
-                        // go back to the shooting phase
-                        self.phase = Phase::Shoot([0.0, -1.0])
-                    }
-                    if r.collided_brick
+                    name: level_data.name.clone(),
+                    pattern: level_data.pattern.clone(),
+                    rules: Rules
                     {
-                        // up the score
-                        self.game_data.score += 1;
-                        // update the score in the forehead
-                        self.forehead.set_score(ctx, self.game_data.score);
-                        // >= for safety
-                        if self.game_data.score >= self.game_data.max_score
-                        {
-                            return Ok(Some(State::LevelClear(LevelClear::new(
-                                ctx,
-                                self.game_data.timer,
-                                self.game_data.level)?)));
-                        }
-                    }
-                }
-                
-                // if pause_transition is still set from the pause phase, and the
-                // 'p' key has been released, unset pause_transition
-                if self.game_data.pause_transition
+                        balls: level_data.balls,
+                        drops: level_data.drops.clone(),
+                        creep: level_data.creep,
+                        speed: 1.0,
+                        // only used in time attack
+                        time_limit: Some(level_data.attack_time()),
+                        par: level_data.par,
+                    },
+                })
+            },
+            Mode::Endless(seed) =>
+            {
+                let w = generate::wave(seed, wave);
+
+                Ok(Stage
                 {
-                    if !self.input_data.p_down
-                    {
-                        self.game_data.pause_transition = false;
-                    }
-                }
-                
-                // if pause_transition is unset, and the 'p' is pressed, go the the pause phase
-                if !self.game_data.pause_transition
+                    name: format!("Wave {} | seed {:08x}", wave, seed),
+                    pattern: w.pattern,
+                    rules: w.rules,
+                })
+            },
+            Mode::Daily(day) =>
+            {
+                let d = generate::daily(day);
+
+                Ok(Stage
                 {
-                    if self.input_data.p_down
-                    {
-                        // change the phase
-                        self.phase = Phase::Pause;
-                        // pause the timer
-                        self.game_data.pause_inst = Some(Instant::now());
-                        // since this pause comes from the key press, set pause_transition
-                        // so that the game won't immediately unpause
-                        self.game_data.pause_transition = true;
-                    }
-                }
+                    name: format!("Daily {}", daily::date(day)),
+                    pattern: d.pattern,
+                    rules: d.rules,
+                })
             },
-            Phase::Pause =>
+        }
+    }
+
+    // a replay is played with the seed it was recorded with, and a new run gets a new one
+    fn seed(playback: &Option<Playback>) -> u64
+    {
+        match playback
+        {
+            Some(ref playback) => playback.replay().seed,
+            None => Rng::random_seed(),
+        }
+    }
+
+    pub fn update(&mut self, ctx: &mut Context, shared: &mut Shared) -> GameResult<Option<State>>
+    {
+        if !self.paused
+        {
+            // the simulation runs at a fixed rate, however often this is called, so
+            // step it as many times as fit in the time since the last update. the
+            // input ticks along with it, so a press only counts for one step
+            for _ in 0..self.timestep.advance(ggez::timer::delta(ctx).as_secs_f32())
             {
-                // if pause_transiton is set, and the 'p' key has been released,
-                // unset pause_transition
-                if self.game_data.pause_transition
+                self.input.tick(&shared.settings.bindings, shared.settings.mouse);
+                if let Some(state) = self.step(ctx, shared)?
                 {
-                    if !self.input_data.p_down
-                    {
-                        self.game_data.pause_transition = false;
-                    }
+                    return Ok(Some(state))
                 }
-                
-                // if the pause_transiton is unset, and the 'p' key is pressed, unpause
-                if !self.game_data.pause_transition
+
+                // you can only pause in the bounce phase
+                if let Phase::Bounce = self.sim.phase()
                 {
-                    if self.input_data.p_down
+                    if self.input.pressed(Action::Pause)
                     {
-                        // change the phase
-                        self.phase = Phase::Bounce;
-                        // if pause_inst is Some, update the pause duration
-                        // (this should always be set, maybe .expect() it?)
-                        if let Some(pause_inst) = self.game_data.pause_inst
-                        {
-                            self.game_data.pause_dur += Instant::now()
-                                .duration_since(pause_inst);
-                            self.game_data.pause_inst = None;
-                        }
-                        // 'p' was pressed, so set pause_transition
-                        self.game_data.pause_transition = true;
+                        self.paused = true;
+                        break;
                     }
                 }
-                
-                // the other way of unpausing, do the same stuff as before but don't
-                // set pause_transition, since the 'p' key wasn't pressed
-                if self.pause_ui.resume_click()
+            }
+
+            // the forehead only re-renders the values that have changed
+            self.forehead.set_score(ctx, self.sim.score().total(), self.sim.multiplier());
+            match self.sim.time_left()
+            {
+                Some(left) => self.forehead.set_time_left(ctx, left),
+                None => self.forehead.set_timer(ctx, self.sim.timer()),
+            }
+            self.forehead.set_lives(self.sim.lives());
+            self.forehead.set_effects(ctx, self.sim.effects());
+        }
+        else
+        {
+            // nothing's stepping, so the input ticks once a frame instead. the launch
+            // key picks from the pause menu once it's let go, so it's already up when
+            // the game carries on
+            self.input.tick(&shared.settings.bindings, shared.settings.mouse);
+            if self.input.pressed(Action::Pause)
+            {
+                self.unpause();
+            }
+            else if self.input.released(Action::Launch)
+            {
+                self.pause_ui.confirm();
+            }
+        }
+
+        if self.paused
+        {
+            // the other way of unpausing
+            if self.pause_ui.resume_click()
+            {
+                self.pause_ui.reset();
+                self.unpause();
+            }
+            if self.pause_ui.restart_click()
+            {
+                // restarts the whole thing
+                self.reset(ctx, shared)?;
+            }
+            if self.pause_ui.main_menu_click()
+            {
+                if self.playback.is_some()
                 {
-                    self.pause_ui.reset();
-                    if let Some(pause_inst) = self.game_data.pause_inst
-                    {
-                        self.game_data.pause_dur += Instant::now()
-                            .duration_since(pause_inst);
-                        self.game_data.pause_inst = None;
-                    }
-                    self.phase = Phase::Bounce;
+                    return Ok(Some(State::ReplaySelect(ReplaySelect::new(ctx, shared, None)?)))
                 }
-                if self.pause_ui.restart_click()
+                return Ok(Some(State::Menu(Menu::new(ctx)?)))
+            }
+        }
+
+        Ok(None)
+    }
+
+    // steps the simulation once, with input from the player or the replay
+    fn step(&mut self, ctx: &mut Context, shared: &mut Shared) -> GameResult<Option<State>>
+    {
+        // a replay supplies its own input
+        let input = match self.playback
+        {
+            Some(ref mut playback) => match playback.next_input()
+            {
+                Some(bits) => InputData::from_bits(bits),
+                None => return self.end_replay(ctx, shared,
+                    Err("the replay ended before the level did".to_string())),
+            },
+            None =>
+            {
+                let held = |action| self.input.held(action);
+                let mouse = shared.settings.mouse;
+                let pointer = match self.mouse_pos
                 {
-                    // restarts the whole thing
-                    self.reset(ctx);
-                }
-                if self.pause_ui.main_menu_click()
+                    Some([x, y]) if mouse != MouseControl::Off =>
+                        Some(Pointer::new(x, y, mouse == MouseControl::Direct)),
+                    _ => None,
+                };
+
+                InputData
                 {
-                    return Ok(Some(State::Menu(Menu::new(ctx)?)))
+                    move_left: held(Action::MoveLeft),
+                    move_right: held(Action::MoveRight),
+                    aim_left: held(Action::AimLeft),
+                    aim_right: held(Action::AimRight),
+                    // once per press, so holding it down doesn't launch the next ball
+                    // as soon as it's ready, or let go of a ball as soon as it sticks
+                    launch: self.input.pressed(Action::Launch),
+                    pointer: pointer,
                 }
             },
+        };
+
+        let outcome = self.sim.update(&input);
+
+        let sim = &self.sim;
+        match self.playback
+        {
+            Some(ref playback) => if let Err(e) = playback.check(|| sim.checksum())
+            {
+                return self.end_replay(ctx, shared, Err(e))
+            },
+            None => self.recorder.record(input.to_bits(), || sim.checksum()),
         }
-        
+
+        // endless mode goes on to the next wave, it only ends when it's lost
+        if let (Some(Outcome::Clear), Mode::Endless(_)) = (outcome, self.mode)
+        {
+            self.next_wave(ctx, shared)?;
+            return Ok(None)
+        }
+
+        let outcome = match outcome
+        {
+            Some(outcome) => outcome,
+            None => return Ok(None),
+        };
+
+        if self.playback.is_some()
+        {
+            return self.end_replay(ctx, shared, self.check_replay_result(outcome))
+        }
+
+        let rank = self.save_result(shared, outcome);
+        let table = self.table(shared);
+
+        match (outcome, self.mode)
+        {
+            // the daily challenge goes back to show how it went
+            (_, Mode::Daily(_)) =>
+            {
+                return Ok(Some(State::Daily(Daily::new(ctx, shared,
+                    rank.map(|rank| (table, rank)))?)))
+            },
+            (Outcome::Clear, Mode::Level(level)) =>
+            {
+                return Ok(Some(State::LevelClear(LevelClear::new(
+                    ctx,
+                    shared,
+                    self.sim.timer(),
+                    level,
+                    self.sim.score(),
+                    table,
+                    rank)?)));
+            },
+            (Outcome::Clear, Mode::Endless(_)) => { },
+            (Outcome::Lose, _) =>
+            {
+                return Ok(Some(State::LevelLose(LevelLose::new(
+                    ctx,
+                    shared,
+                    self.sim.timer(),
+                    self.sim.score().total(),
+                    self.sim.hits(),
+                    self.sim.max_hits(),
+                    self.mode,
+                    table,
+                    rank)?)));
+            },
+        }
+
         Ok(None)
     }
 
-    fn reset(&mut self, ctx: &mut Context)
+    // a cleared wave of endless mode is replaced by the next one
+    fn next_wave(&mut self, ctx: &mut Context, shared: &Shared) -> GameResult<()>
     {
-        self.paddle.reset();
-        self.ball = None;
-        self.bricks.reset();
-        self.forehead.reset(ctx);
-        self.pause_ui.reset();
+        self.wave += 1;
+        let stage = Game::stage(shared, &self.mode, self.wave)?;
+
+        // creep mode carries on through every wave
+        let mut rules = stage.rules;
+        rules.creep = self.sim.creep();
+        self.sim.next_wave(Bricks::new(Bricks::make_pattern(stage.pattern)), rules);
 
-        self.phase = Phase::Shoot(utils::normalize([0.0, -1.0]));
+        self.forehead.set_level(ctx, &stage.name);
 
-        self.game_data = GameData
+        Ok(())
+    }
+
+    // the high score table for what's being played, with the modes it's played in
+    fn table(&self, shared: &Shared) -> Table
+    {
+        let modes = Modes
         {
-            paddle_speed: 3.5f32,
-            pause_transition: false,
-            score: 0,
-            level: self.game_data.level,
-            max_score: self.bricks.total(),
-            timer: 0,
-            lives: STARTING_LIVES,
-            start_inst: None,
-            pause_inst: None,
-            pause_dur: Duration::new(0, 0),
+            creep: self.sim.creep().is_some(),
+            time_attack: self.sim.time_limit().is_some(),
         };
-        self.input_data = InputData
+
+        match self.mode
         {
-            left_down: false,
-            right_down: false,
-            enter_down: false,
-            p_down: false,
+            Mode::Level(level) => match shared.levels.get(level)
+            {
+                Some(l) => Table::Level(l.id.clone(), modes),
+                None => Table::Level(level.to_string(), modes),
+            },
+            Mode::Endless(seed) => Table::Endless(seed, modes),
+            Mode::Daily(day) => Table::Daily(day),
+        }
+    }
+
+    // records the result of the level in the save and the high scores, and writes out
+    // the replay. it gives where the score placed in its high score table, if it made it
+    fn save_result(&mut self, shared: &mut Shared, outcome: Outcome) -> Option<usize>
+    {
+        let source = match self.mode
+        {
+            Mode::Level(level) => match shared.levels.get(level)
+            {
+                Some(l) => Source::Level(l.id.clone(), l.checksum()),
+                None => return None,
+            },
+            Mode::Endless(seed) => Source::Endless(seed),
+            Mode::Daily(day) => Source::Daily(day),
         };
+
+        let score = self.sim.score().total();
+        match (&source, outcome)
+        {
+            (Source::Level(id, _), Outcome::Clear) => shared.save.record_clear(
+                id,
+                self.sim.timer(),
+                score,
+                sim::STARTING_LIVES - self.sim.lives()),
+            (Source::Level(id, _), Outcome::Lose) => shared.save.record_lose(id, score),
+            (Source::Endless(seed), _) => shared.save.record_endless(*seed, score, self.wave),
+            (Source::Daily(day), _) => shared.save.record_daily(
+                *day,
+                outcome == Outcome::Clear,
+                self.sim.timer(),
+                score,
+                self.sim.lives()),
+        }
+
+        shared.save.write();
+
+        // the score goes in under the last name used, the screen after the game can
+        // change it
+        let rank = shared.scores.insert(self.table(shared), Entry
+        {
+            score: score,
+            time: self.sim.timer(),
+            name: shared.settings.name.clone(),
+        });
+        shared.scores.write();
+
+        let recorder = std::mem::replace(&mut self.recorder,
+            Recorder::new(self.sim.seed(), self.sim.creep(), self.sim.time_limit()));
+        recorder.finish(
+            source,
+            outcome == Outcome::Clear,
+            score,
+            self.sim.timer(),
+            self.sim.checksum())
+            .write(&Replay::dir(&shared.config_dir));
+
+        rank
     }
 
-    pub fn key_down(&mut self, key: KeyCode, _repeat: bool)
+    // a finished replay should have ended the same way, at the same step
+    fn check_replay_result(&self, outcome: Outcome) -> Result<(), String>
     {
-        match key
+        let playback = match self.playback
         {
-            KeyCode::Left => self.input_data.left_down = true,
-            KeyCode::Right => self.input_data.right_down = true,
-            KeyCode::Return => self.input_data.enter_down = true,
-            KeyCode::P => self.input_data.p_down = true,
-            _ => { }
+            Some(ref playback) => playback,
+            None => return Ok(()),
+        };
+        let replay = playback.replay();
+
+        if !playback.finished()
+        {
+            Err("the level ended before the replay did".to_string())
+        }
+        else if (outcome == Outcome::Clear) != replay.cleared
+            || self.sim.score().total() != replay.score
+            || self.sim.timer() != replay.time
+        {
+            Err("the replay finished with a different result".to_string())
+        }
+        else
+        {
+            Ok(())
+        }
+    }
+
+    // goes back to the replays, with whatever went wrong
+    fn end_replay(&self, ctx: &mut Context, shared: &Shared, result: Result<(), String>)
+        -> GameResult<Option<State>>
+    {
+        let message = match result
+        {
+            Ok(()) => "The replay played back exactly".to_string(),
+            Err(e) => format!("Error: {}", e),
+        };
+
+        Ok(Some(State::ReplaySelect(ReplaySelect::new(ctx, shared, Some(message))?)))
+    }
+
+    fn unpause(&mut self)
+    {
+        self.paused = false;
+    }
+
+    fn reset(&mut self, ctx: &mut Context, shared: &Shared) -> GameResult<()>
+    {
+        let (sim, name) = Game::start(shared, &self.mode, &self.playback)?;
+        self.sim = sim;
+        self.wave = 1;
+
+        self.forehead.reset(ctx);
+        self.forehead.set_level(ctx, &name);
+        self.pause_ui.reset();
+
+        self.paused = false;
+
+        self.input.clear();
+
+        self.timestep.reset();
+
+        // a restart is a new run, and a replay starts over
+        self.recorder = Recorder::new(self.sim.seed(), self.sim.creep(), self.sim.time_limit());
+        if let Some(ref mut playback) = self.playback
+        {
+            playback.restart();
+        }
+
+        Ok(())
+    }
+
+    // while paused the keys move around the pause menu too, but return is left to
+    // the launch action. escape pauses like the pause key does
+    pub fn key_down(&mut self, key: KeyCode, repeat: bool)
+    {
+        self.input.key_down(key);
+        if self.paused
+        {
+            if !matches!(key, KeyCode::Return | KeyCode::NumpadEnter)
+            {
+                self.pause_ui.key_down(key, repeat);
+            }
+        }
+        else if key == KeyCode::Escape && !repeat
+        {
+            if let Phase::Bounce = self.sim.phase()
+            {
+                self.paused = true;
+            }
         }
     }
 
     pub fn key_up(&mut self, key: KeyCode)
     {
-        match key
+        self.input.key_up(key);
+        self.pause_ui.key_up(key);
+    }
+
+    // the gamepad as menu keys, which only matter to the pause menu. A is already
+    // the launch action, so it isn't return here too
+    pub fn pad_key(&mut self, key: KeyCode, down: bool)
+    {
+        if key == KeyCode::Return
+        {
+            return
+        }
+        if down && self.paused
         {
-            KeyCode::Left => self.input_data.left_down = false,
-            KeyCode::Right => self.input_data.right_down = false,
-            KeyCode::Return => self.input_data.enter_down = false,
-            KeyCode::P => self.input_data.p_down = false,
-            _ => { }
+            self.pause_ui.key_down(key, false);
+        }
+        else if !down
+        {
+            self.pause_ui.key_up(key);
         }
     }
 
+    pub fn pad_down(&mut self, button: PadButton)
+    {
+        self.input.pad_down(button);
+    }
+
+    pub fn pad_up(&mut self, button: PadButton)
+    {
+        self.input.pad_up(button);
+    }
+
+    pub fn pad_axis(&mut self, axis: Axis, value: f32)
+    {
+        self.input.pad_axis(axis, value);
+    }
+
     pub fn mouse_move(&mut self, x: f32, y: f32)
     {
-        match self.phase
+        self.mouse_pos = Some([x, y]);
+        if self.paused
         {
-            Phase::Pause =>
-            {
-                self.pause_ui.mouse_move(x, y);
-            },
-            _ => { }
+            self.pause_ui.mouse_move(x, y);
         }
     }
 
     pub fn mouse_down(&mut self, button: MouseButton, x: f32, y: f32)
     {
-        match self.phase
+        if self.paused
         {
-            Phase::Pause =>
-            {
-                self.pause_ui.mouse_down(button, x, y);
-            },
-            _ => { }
+            self.pause_ui.mouse_down(button, x, y);
+        }
+        else
+        {
+            self.input.mouse_down(button);
         }
     }
 
     pub fn mouse_up(&mut self, button: MouseButton)
     {
-        match self.phase
+        self.input.mouse_up(button);
+        if self.paused
         {
-            Phase::Pause =>
-            {
-                self.pause_ui.mouse_up(button);
-            },
-            _ => { }
+            self.pause_ui.mouse_up(button);
         }
     }
 
@@ -416,82 +730,42 @@ impl Game
         // the paddle, bricks, ball, and forehead are always drawn,
         // we draw them before so that if something (like the pause menu)
         // wants to cover them it can
-        self.paddle.draw(ctx)?;
-        if let Some(ref ball) = &self.ball
+        // draw the simulation part of the way to its next step
+        let alpha = self.timestep.alpha();
+        self.renderer.draw(ctx, &self.sim, alpha)?;
+        self.forehead.draw(ctx)?;
+
+        // in she shoot phase, draw the ball shooter
+        if let Phase::Shoot(ref angle) = self.sim.phase()
         {
-            ball.draw(ctx)?;
+            use ggez::graphics;
+
+            let paddle = self.sim.paddle().rect();
+            let start =
+            [
+                self.sim.paddle().lerp_x(alpha) + paddle.w / 2.0,
+                paddle.y
+            ];
+
+            // it's a line
+            let line = graphics::Mesh::new_line(
+                ctx,
+                &[
+                    start,
+                    [start[0] + angle[0] * LINE_LENGTH, start[1] + angle[1] * LINE_LENGTH]
+                ],
+                1.0,
+                graphics::BLACK)?;
+
+            graphics::draw(ctx, &line, graphics::DrawParam::new())?;
         }
-        self.bricks.draw(ctx)?;
-        self.forehead.draw(ctx)?;
 
-        match &self.phase
+        // when paused we draw the pause menu
+        if self.paused
         {
-            // in she shoot phase, draw the ball shooter 
-            Phase::Shoot(ref angle) =>
-            {
-                use ggez::graphics;
-
-                let start =
-                [
-                    self.paddle.rect().x + self.paddle.rect().w / 2.0,
-                    self.paddle.rect().y
-                ];
-                
-                // it's a line
-                let line = graphics::Mesh::new_line(
-                    ctx,
-                    &[
-                        start,
-                        [start[0] + angle[0] * LINE_LENGTH, start[1] + angle[1] * LINE_LENGTH]
-                    ],
-                    1.0,
-                    graphics::BLACK)?;
-
-                graphics::draw(ctx, &line, graphics::DrawParam::new())?;
-            },
-            // the bounce phase has nothing special to draw, as everything is already drawn
-            Phase::Bounce => { },
-            // in this phase we draw the pause menu
-            Phase::Pause =>
-            {
-                self.pause_ui.draw(ctx)?;
-            },
+            self.pause_ui.draw(ctx)?;
         }
 
         Ok(())
     }
 }
-
-struct GameData
-{
-    score: u32,
-    max_score: u32,
-    timer: u32,
-    level: u32,
-
-    lives: u32,
-    paddle_speed: f32,
-    pause_transition: bool,
-    
-    // the time at the start of the game
-    start_inst: Option<Instant>,
-    // the last time the timer was paused
-    pause_inst: Option<Instant>,
-    // the total pause time of the game
-    pause_dur: Duration,
-}
-
-struct InputData
-{
-    left_down: bool,
-    right_down: bool,
-    enter_down: bool,
-    p_down: bool,
-}
-
-enum Phase
-{
-    Shoot([f32; 2]),
-    Bounce,
-    Pause,
-}
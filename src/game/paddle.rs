@@ -1,35 +1,26 @@
-use ggez::
-{
-    Context,
-    GameResult,
-    graphics::
-    {
-        self,
-        Rect,
-        Mesh,
-        DrawMode,
-        DrawParam,
-    },
-};
+use ggez::graphics::Rect;
 
 use crate::{FOREHEAD, BOARD_HEIGHT, BOARD_WIDTH};
 
 // the default paddle dimensions
-const PADDLE_WIDTH: f32 = 65f32;
-const PADDLE_HEIGHT: f32 = 20f32;
+pub const PADDLE_WIDTH: f32 = 65f32;
+pub const PADDLE_HEIGHT: f32 = 20f32;
 
 // the paddle's y position
 const PADDLE_Y: f32 = (BOARD_HEIGHT * 0.9 + FOREHEAD) - PADDLE_HEIGHT / 2.0;
 
+#[derive(Clone)]
 pub struct Paddle
 {
     rect: Rect,
-    mesh: Mesh,
+
+    // the x position before the last step
+    prev_x: f32,
 }
 
 impl Paddle
 {
-    pub fn new(ctx: &mut Context) -> GameResult<Paddle>
+    pub fn new() -> Paddle
     {
         // the paddle starts in the middle of the board
         let rect = Rect::new(
@@ -38,17 +29,12 @@ impl Paddle
             PADDLE_WIDTH,
             PADDLE_HEIGHT);
 
-        Ok(Paddle
+        Paddle
         {
             rect: rect,
-            mesh: Mesh::new_rectangle(
-                ctx,
-                DrawMode::fill(),
-                // the mesh is at (0, 0), because we draw it with the rect
-                // coords so that it matches correctly
-                Rect::new(0f32, 0f32, PADDLE_WIDTH, PADDLE_HEIGHT),
-                graphics::BLACK)?,
-        })
+
+            prev_x: rect.x,
+        }
     }
 
     pub fn reset(&mut self)
@@ -58,6 +44,27 @@ impl Paddle
             PADDLE_Y,
             PADDLE_WIDTH,
             PADDLE_HEIGHT);
+        self.prev_x = self.rect.x;
+    }
+
+    pub fn begin_step(&mut self)
+    {
+        self.prev_x = self.rect.x;
+    }
+
+    // the x position part of the way between the last step and this one
+    pub fn lerp_x(&self, alpha: f32) -> f32
+    {
+        self.prev_x + (self.rect.x - self.prev_x) * alpha
+    }
+
+    // changes the width, keeping the paddle's middle where it is
+    pub fn set_width(&mut self, w: f32)
+    {
+        let middle = self.rect.x + self.rect.w / 2.0;
+        self.rect.w = w;
+        self.rect.x = middle - w / 2.0;
+        self.shift(0.0);
     }
 
     pub fn shift(&mut self, dx: f32)
@@ -79,11 +86,4 @@ impl Paddle
     {
         self.rect
     }
-
-    pub fn draw(&self, ctx: &mut Context) -> GameResult<()>
-    {
-        graphics::draw(ctx, &self.mesh, DrawParam::default()
-            // draw the mesh where the paddle rect is
-            .dest([self.rect.x, self.rect.y]))
-    }
 }
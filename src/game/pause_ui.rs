@@ -11,11 +11,15 @@ use ggez::
         DrawMode,
         DrawParam,
     },
-    input::mouse::MouseButton,
+    input::
+    {
+        keyboard::KeyCode,
+        mouse::MouseButton,
+    },
 };
 
 use crate::{FOREHEAD, BOARD_WIDTH};
-use crate::ui::{TextRect, Button};
+use crate::ui::{TextRect, Button, focus::{self, FocusKey}};
 
 pub struct PauseUI
 {
@@ -103,6 +107,12 @@ impl PauseUI
         self.main_menu.reset();
     }
 
+    // the daily challenge can't be restarted
+    pub fn set_restart_locked(&mut self, locked: bool)
+    {
+        self.restart.set_locked(locked);
+    }
+
     pub fn resume_click(&self) -> bool
     {
         self.resume.click()
@@ -118,6 +128,27 @@ impl PauseUI
         self.main_menu.click()
     }
 
+    // escape resumes, like pressing pause again
+    pub fn key_down(&mut self, key: KeyCode, repeat: bool)
+    {
+        if focus::key_down(&mut [&mut self.resume, &mut self.restart, &mut self.main_menu],
+            key, repeat) == FocusKey::Back
+        {
+            self.resume.press();
+        }
+    }
+
+    // presses the focused button, for the launch key
+    pub fn confirm(&mut self)
+    {
+        focus::confirm(&mut [&mut self.resume, &mut self.restart, &mut self.main_menu]);
+    }
+
+    pub fn key_up(&mut self, key: KeyCode)
+    {
+        focus::key_up(&mut [&mut self.resume, &mut self.restart, &mut self.main_menu], key);
+    }
+
     pub fn mouse_move(&mut self, x: f32, y: f32)
     {
         self.resume.mouse_move(x, y);
@@ -0,0 +1,199 @@
+use ggez::graphics::{Rect, Color};
+
+use crate::{BOARD_HEIGHT, FOREHEAD};
+
+use super::sim::TICKS_PER_SECOND;
+
+pub const CAPSULE_WIDTH: f32 = 24f32;
+pub const CAPSULE_HEIGHT: f32 = 12f32;
+// in pixels per second
+const CAPSULE_SPEED: f32 = 90f32;
+
+// what a capsule does when the paddle catches it
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Effect
+{
+    // the paddle gets wider
+    Wide,
+    // the paddle gets narrower
+    Narrow,
+    // the balls slow down
+    Slow,
+    // one more life
+    Life,
+    // more balls
+    Multi,
+    // balls stick to the paddle until they're launched again
+    Sticky,
+    // more time, in time attack mode
+    Time,
+}
+
+impl Effect
+{
+    pub const ALL: [Effect; 7] =
+    [
+        Effect::Wide,
+        Effect::Narrow,
+        Effect::Slow,
+        Effect::Life,
+        Effect::Multi,
+        Effect::Sticky,
+        Effect::Time,
+    ];
+
+    // the name used in level files
+    pub fn name(self) -> &'static str
+    {
+        match self
+        {
+            Effect::Wide => "wide",
+            Effect::Narrow => "narrow",
+            Effect::Slow => "slow",
+            Effect::Life => "life",
+            Effect::Multi => "multi",
+            Effect::Sticky => "sticky",
+            Effect::Time => "time",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Effect>
+    {
+        Effect::ALL.iter().copied().find(|e| e.name() == name)
+    }
+
+    // the letter drawn on the capsule and in the forehead, and used for bricks
+    // in level files
+    pub fn letter(self) -> char
+    {
+        match self
+        {
+            Effect::Wide => 'W',
+            Effect::Narrow => 'N',
+            Effect::Slow => 'S',
+            Effect::Life => 'L',
+            Effect::Multi => 'M',
+            Effect::Sticky => 'C',
+            Effect::Time => 'T',
+        }
+    }
+
+    pub fn from_letter(c: char) -> Option<Effect>
+    {
+        Effect::ALL.iter().copied().find(|e| e.letter() == c.to_ascii_uppercase())
+    }
+
+    // how many steps the effect lasts, or None if it happens once when it's caught
+    pub fn duration(self) -> Option<u32>
+    {
+        match self
+        {
+            Effect::Wide | Effect::Narrow | Effect::Sticky => Some(15 * TICKS_PER_SECOND),
+            Effect::Slow => Some(10 * TICKS_PER_SECOND),
+            Effect::Life | Effect::Multi | Effect::Time => None,
+        }
+    }
+
+    pub fn color(self) -> Color
+    {
+        match self
+        {
+            Effect::Wide => Color::from_rgb(0, 160, 255),
+            Effect::Narrow => Color::from_rgb(255, 96, 0),
+            Effect::Slow => Color::from_rgb(0, 200, 120),
+            Effect::Life => Color::from_rgb(255, 64, 128),
+            Effect::Multi => Color::from_rgb(160, 64, 255),
+            Effect::Sticky => Color::from_rgb(200, 170, 0),
+            Effect::Time => Color::from_rgb(0, 210, 230),
+        }
+    }
+
+    // a number for checksums
+    pub fn code(self) -> u32
+    {
+        Effect::ALL.iter().position(|e| *e == self).map(|i| i as u32 + 1).unwrap_or(0)
+    }
+}
+
+// a falling capsule, dropped by a broken brick
+#[derive(Copy, Clone)]
+pub struct Capsule
+{
+    rect: Rect,
+    effect: Effect,
+
+    // the y position before the last step
+    prev_y: f32,
+}
+
+impl Capsule
+{
+    // the capsule starts centered on pos
+    pub fn new(pos: [f32; 2], effect: Effect) -> Capsule
+    {
+        let rect = Rect::new(
+            pos[0] - CAPSULE_WIDTH / 2.0,
+            pos[1] - CAPSULE_HEIGHT / 2.0,
+            CAPSULE_WIDTH,
+            CAPSULE_HEIGHT);
+
+        Capsule
+        {
+            rect: rect,
+            effect: effect,
+
+            prev_y: rect.y,
+        }
+    }
+
+    pub fn begin_step(&mut self)
+    {
+        self.prev_y = self.rect.y;
+    }
+
+    // the position part of the way between the last step and this one
+    pub fn lerp_pos(&self, alpha: f32) -> [f32; 2]
+    {
+        [self.rect.x, self.prev_y + (self.rect.y - self.prev_y) * alpha]
+    }
+
+    // falls for a step, returns false once it's off the bottom of the board
+    pub fn update(&mut self, dt: f32) -> bool
+    {
+        self.rect.y += CAPSULE_SPEED * dt;
+        self.rect.y < FOREHEAD + BOARD_HEIGHT
+    }
+
+    pub fn rect(&self) -> Rect
+    {
+        self.rect
+    }
+
+    pub fn effect(&self) -> Effect
+    {
+        self.effect
+    }
+}
+
+// which capsules a level drops
+#[derive(Clone)]
+pub struct Drops
+{
+    // the chance that a brick without its own drop drops something when it breaks
+    pub chance: f32,
+    // what those bricks can drop, picked evenly
+    pub effects: Vec<Effect>,
+}
+
+impl Default for Drops
+{
+    // every effect, now and then
+    fn default() -> Drops
+    {
+        Drops
+        {
+            chance: 0.1,
+            effects: Effect::ALL.to_vec(),
+        }
+    }
+}
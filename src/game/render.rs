@@ -0,0 +1,146 @@
+use ggez::
+{
+    Context,
+    GameResult,
+    graphics::
+    {
+        self,
+        Rect,
+        Mesh,
+        Text,
+        Font,
+        Scale,
+        DrawMode,
+        DrawParam,
+    },
+};
+
+use super::sim::{self, Sim};
+use super::paddle::{PADDLE_WIDTH, PADDLE_HEIGHT};
+use super::ball::BALL_SIZE;
+use super::bricks::{Bricks, BRICK_WIDTH, BRICK_HEIGHT, EXPLOSION_RADIUS};
+use super::powerup::{Effect, CAPSULE_WIDTH, CAPSULE_HEIGHT};
+
+// the meshes for everything in the simulation, it only ever reads the simulation
+pub struct Renderer
+{
+    paddle: Mesh,
+    ball: Mesh,
+    brick: Mesh,
+    capsule: Mesh,
+    explosion: Mesh,
+    // the letter for each effect, in the order of Effect::ALL, and where it goes
+    // on the capsule
+    letters: Vec<(Text, [f32; 2])>,
+}
+
+impl Renderer
+{
+    pub fn new(ctx: &mut Context) -> GameResult<Renderer>
+    {
+        let letters = Effect::ALL.iter()
+            .map(|e|
+            {
+                let mut t = Text::new(e.letter().to_string());
+                t.set_font(Font::default(), Scale::uniform(CAPSULE_HEIGHT));
+                let (w, h) = t.dimensions(ctx);
+                let offset =
+                [
+                    (CAPSULE_WIDTH - w as f32) / 2.0,
+                    (CAPSULE_HEIGHT - h as f32) / 2.0,
+                ];
+                (t, offset)
+            })
+            .collect();
+
+        Ok(Renderer
+        {
+            paddle: Mesh::new_rectangle(
+                ctx,
+                DrawMode::fill(),
+                // the mesh is at (0, 0), because we draw it with the rect
+                // coords so that it matches correctly
+                Rect::new(0f32, 0f32, PADDLE_WIDTH, PADDLE_HEIGHT),
+                graphics::BLACK)?,
+            ball: Mesh::new_circle(
+                ctx,
+                DrawMode::fill(),
+                // the mesh is at (0, 0) because we draw it at the ball position
+                // at draw time
+                [0f32, 0f32],
+                BALL_SIZE,
+                0.1,
+                graphics::BLACK)?,
+            brick: Mesh::new_rectangle(
+                ctx,
+                DrawMode::fill(),
+                Rect::new(0f32, 0f32, BRICK_WIDTH, BRICK_HEIGHT),
+                graphics::WHITE)?,
+            // white so it can be drawn in the effect's color
+            capsule: Mesh::new_rectangle(
+                ctx,
+                DrawMode::fill(),
+                Rect::new(0f32, 0f32, CAPSULE_WIDTH, CAPSULE_HEIGHT),
+                graphics::WHITE)?,
+            explosion: Mesh::new_circle(
+                ctx,
+                DrawMode::fill(),
+                [0f32, 0f32],
+                EXPLOSION_RADIUS,
+                0.5,
+                graphics::WHITE)?,
+            letters: letters,
+        })
+    }
+
+    // alpha is how far it is between the last step and the next one, things that move
+    // are drawn that far between where they were and where they are
+    pub fn draw(&self, ctx: &mut Context, sim: &Sim, alpha: f32) -> GameResult<()>
+    {
+        // draw the mesh where the paddle rect is, stretched if the paddle's
+        // width has changed
+        let paddle = sim.paddle();
+        graphics::draw(ctx, &self.paddle, DrawParam::default()
+            .dest([paddle.lerp_x(alpha), paddle.rect().y])
+            .scale([paddle.rect().w / PADDLE_WIDTH, 1.0]))?;
+
+        for ball in sim.balls()
+        {
+            graphics::draw(ctx, &self.ball, DrawParam::new()
+                .dest(ball.lerp_pos(alpha)))?;
+        }
+
+        // invisible bricks aren't drawn until they've been hit
+        for brick in sim.bricks().iter().filter(|b| b.visible)
+        {
+            graphics::draw(ctx, &self.brick, DrawParam::new()
+                .dest(brick.lerp_pos(alpha))
+                .color(Bricks::get_color(brick.kind, brick.count)))?;
+        }
+
+        // explosions fade out over the steps they're shown for
+        for (pos, left) in sim.explosions()
+        {
+            let fade = *left as f32 / sim::EXPLOSION_STEPS as f32;
+            graphics::draw(ctx, &self.explosion, DrawParam::new()
+                .dest(*pos)
+                .color(graphics::Color::new(1.0, 0.5, 0.0, fade * 0.6)))?;
+        }
+
+        for capsule in sim.capsules()
+        {
+            let pos = capsule.lerp_pos(alpha);
+            let effect = capsule.effect();
+            graphics::draw(ctx, &self.capsule, DrawParam::new()
+                .dest(pos)
+                .color(effect.color()))?;
+
+            let (ref letter, offset) = self.letters[effect.code() as usize - 1];
+            graphics::draw(ctx, letter, DrawParam::new()
+                .dest([pos[0] + offset[0], pos[1] + offset[1]])
+                .color(graphics::BLACK))?;
+        }
+
+        Ok(())
+    }
+}
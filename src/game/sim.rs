@@ -0,0 +1,1247 @@
+use crate::checksum::Checksum;
+use crate::rng::Rng;
+use crate::{BOARD_WIDTH, BOARD_HEIGHT, FOREHEAD};
+
+use super::paddle::{self, Paddle};
+use super::ball::{self, Ball};
+use super::bricks::{Bricks, Brick, BRICK_HEIGHT};
+use super::powerup::{Effect, Capsule, Drops};
+use super::utils;
+
+// speeds are per second, and scaled by DT each step
+const ANGLE_CHANGE: f32 = 2.4;
+const PADDLE_SPEED: f32 = 210.0;
+// the angle between balls that are launched together, in radians
+const BALL_SPREAD: f32 = 0.2;
+
+pub const STARTING_LIVES: u32 = 3;
+// extra lives can't go over this
+pub const MAX_LIVES: u32 = 5;
+// multi-ball doesn't add more once there are this many
+const MAX_BALLS: usize = 16;
+// the angle the extra balls from multi-ball go off at, in radians
+const MULTI_SPREAD: f32 = 0.35;
+// how much the wide and narrow capsules change the paddle width by
+const WIDE: f32 = 1.5;
+const NARROW: f32 = 0.65;
+// how much the slow capsule changes the ball speed by
+const SLOW: f32 = 0.6;
+// the steps added to the clock in time attack for each broken brick, and for
+// catching a time capsule
+const BRICK_TIME: u32 = TICKS_PER_SECOND;
+const CAPSULE_TIME: u32 = 10 * TICKS_PER_SECOND;
+// the combo multiplier goes up by one for every COMBO_STEP bricks hit without a ball
+// touching the paddle, up to MAX_MULTIPLIER
+const COMBO_STEP: u32 = 5;
+const MAX_MULTIPLIER: u32 = 5;
+// the bonus points when a level is cleared, for each second left on the clock (or
+// under par) and for each life left
+const TIME_POINTS: u32 = 20;
+const LIFE_POINTS: u32 = 250;
+// how many steps an explosion is shown for
+pub const EXPLOSION_STEPS: u32 = TICKS_PER_SECOND / 4;
+// the simulation always steps this many times a second, whatever the frame rate
+pub const TICKS_PER_SECOND: u32 = 120;
+// the length of one step, in seconds
+pub const DT: f32 = 1.0 / TICKS_PER_SECOND as f32;
+
+// the gameplay itself, without anything that needs a window: the paddle, balls, and bricks,
+// the lives, progress and score, and the phases of play. the game reads this to draw it
+#[derive(Clone)]
+pub struct Sim
+{
+    paddle: Paddle,
+    // every ball in play, a life is only lost when the last one is gone
+    balls: Vec<Ball>,
+    bricks: Bricks,
+    capsules: Vec<Capsule>,
+    // explosions that are still being shown, and the steps they have left
+    explosions: Vec<([f32; 2], u32)>,
+
+    rules: Rules,
+    // capsules are dropped at random, from this seed
+    seed: u64,
+    rng: Rng,
+
+    phase: Phase,
+
+    data: SimData,
+}
+
+impl Sim
+{
+    pub fn new(bricks: Bricks, rules: Rules, seed: u64) -> Sim
+    {
+        let max_hits = bricks.total();
+        let rules = rules.checked();
+        let time_left = rules.time_left();
+
+        Sim
+        {
+            paddle: Paddle::new(),
+            balls: Vec::new(),
+            bricks: bricks,
+            capsules: Vec::new(),
+            explosions: Vec::new(),
+
+            rules: rules,
+            seed: seed,
+            rng: Rng::new(seed),
+
+            phase: Phase::Shoot(utils::normalize([0.0, -1.0])),
+
+            data: SimData::new(max_hits, time_left),
+        }
+    }
+
+    // starts the next wave of endless mode on new bricks, the score, lives and
+    // timer carry on
+    pub fn next_wave(&mut self, bricks: Bricks, rules: Rules)
+    {
+        self.paddle.reset();
+        self.balls.clear();
+        self.bricks = bricks;
+        self.capsules.clear();
+        self.explosions.clear();
+
+        self.rules = rules.checked();
+
+        self.phase = Phase::Shoot(utils::normalize([0.0, -1.0]));
+
+        self.data.max_hits += self.bricks.total();
+        self.data.effects.clear();
+        self.data.creep = 0;
+        self.data.time_left = self.rules.time_left();
+    }
+
+    // steps the simulation once, returns Some if the level is over
+    pub fn update(&mut self, input: &InputData) -> Option<Outcome>
+    {
+        // remember where things were, so they can be drawn between steps
+        self.paddle.begin_step();
+        for ball in self.balls.iter_mut()
+        {
+            ball.begin_step();
+        }
+        for capsule in self.capsules.iter_mut()
+        {
+            capsule.begin_step();
+        }
+        self.bricks.begin_step();
+
+        match &mut self.phase
+        {
+            // the game starts in this phase, and returns to it when every ball is destroyed
+            Phase::Shoot(ref mut angle) =>
+            {
+                // the mouse points the shooter at itself, unless it's being aimed with
+                // the keys
+                let aiming = input.aim_left || input.aim_right;
+                if let Some(pointer) = input.pointer.filter(|_| !aiming)
+                {
+                    let paddle = self.paddle.rect();
+                    let [x, y] = pointer.pos();
+                    // the cursor below the paddle points along it, then the clamp
+                    // keeps it off the horizontal
+                    let d = [x - (paddle.x + paddle.w / 2.0), f32::min(y - paddle.y, 0.0)];
+                    if d != [0.0, 0.0]
+                    {
+                        *angle = utils::normalize(d);
+                    }
+                }
+
+                // rotate the angle of the ball shooter
+                if input.aim_left
+                {
+                    *angle = utils::rotate(*angle, -ANGLE_CHANGE * DT);
+                }
+                if input.aim_right
+                {
+                    *angle = utils::rotate(*angle, ANGLE_CHANGE * DT);
+                }
+
+                *angle = Sim::clamp_angle(*angle);
+
+                // shoot the ball
+                if input.launch
+                {
+                    let angle = *angle;
+                    self.launch(angle);
+                }
+            },
+            // this is the main gameplay phase
+            Phase::Bounce =>
+            {
+                // the timer only runs while the ball is in play, and is in seconds only
+                self.data.ticks += 1;
+                self.data.timer = self.data.ticks / TICKS_PER_SECOND;
+
+                // in time attack there's a clock running down too, and when it's out
+                // so is the level
+                if let Some(ref mut left) = self.data.time_left
+                {
+                    *left = left.saturating_sub(1);
+                    if *left == 0
+                    {
+                        return Some(Outcome::Lose)
+                    }
+                }
+
+                // move the paddle, the paddle will handle confining itself to the board
+                if input.move_left
+                {
+                    self.paddle.shift(-self.data.paddle_speed * DT);
+                }
+                if input.move_right
+                {
+                    self.paddle.shift(self.data.paddle_speed * DT);
+                }
+                // or it follows the mouse, at its usual speed or all at once
+                if let Some(pointer) = input.pointer
+                {
+                    let paddle = self.paddle.rect();
+                    let dx = pointer.pos()[0] - (paddle.x + paddle.w / 2.0);
+                    let max = self.data.paddle_speed * DT;
+                    self.paddle.shift(if pointer.direct { dx } else { dx.clamp(-max, max) });
+                }
+
+                // launch any balls stuck to the paddle
+                if input.launch
+                {
+                    for ball in self.balls.iter_mut()
+                    {
+                        ball.release(&self.paddle);
+                    }
+                }
+
+                // run down the effects, and get rid of the ones that are over
+                for (_, left) in self.data.effects.iter_mut()
+                {
+                    *left -= 1;
+                }
+                self.data.effects.retain(|(_, left)| *left > 0);
+                self.apply_effects();
+
+                self.bricks.update(self.data.ticks);
+
+                // the bricks creep down before the balls move, and if they reach the
+                // paddle it's over
+                if let Some(creep) = self.rules.creep
+                {
+                    if self.data.creep >= creep.steps()
+                    {
+                        self.data.creep = 0;
+                        self.bricks.descend(BRICK_HEIGHT);
+                    }
+                    if self.bricks.lowest().is_some_and(|y| y >= self.paddle.rect().top())
+                    {
+                        return Some(Outcome::Lose)
+                    }
+                }
+                for (_, left) in self.explosions.iter_mut()
+                {
+                    *left -= 1;
+                }
+                self.explosions.retain(|(_, left)| *left > 0);
+
+                // the ball update handles all bouncing, including going off the board
+                // and being destroyed, and bouncing off of / breaking bricks.
+                // balls are updated in order, so a brick one ball breaks is gone
+                // for the next
+                let paddle = &self.paddle;
+                let bricks = &mut self.bricks;
+                let sticky = self.data.active(Effect::Sticky);
+                let mut bricks_hit = 0;
+                let mut points = 0;
+                let mut paddle_hits = 0;
+                let mut broken = Vec::new();
+                let explosions = &mut self.explosions;
+                self.balls.retain_mut(|ball|
+                {
+                    let r = ball.update(paddle, bricks, DT, sticky);
+                    bricks_hit += r.bricks_hit;
+                    points += r.points;
+                    paddle_hits += r.paddle_hits;
+                    broken.extend(r.broken);
+                    explosions.extend(r.explosions.into_iter().map(|e| (e, EXPLOSION_STEPS)));
+                    // get rid of destroyed balls so they aren't drawn
+                    !r.destroyed_ball
+                });
+
+                for brick in &broken
+                {
+                    self.drop_capsule(brick);
+                }
+                if let Some(ref mut left) = self.data.time_left
+                {
+                    *left += broken.len() as u32 * BRICK_TIME;
+                }
+
+                if let Some(creep) = self.rules.creep
+                {
+                    self.data.creep += match creep
+                    {
+                        Creep::Seconds(_) => 1,
+                        Creep::Hits(_) => paddle_hits,
+                    };
+                }
+
+                // capsules fall, and are caught if they touch the paddle
+                let mut caught = Vec::new();
+                let paddle = self.paddle.rect();
+                self.capsules.retain_mut(|capsule|
+                {
+                    if capsule.rect().overlaps(&paddle)
+                    {
+                        caught.push(capsule.effect());
+                        return false
+                    }
+                    capsule.update(DT)
+                });
+                for effect in caught
+                {
+                    self.catch(effect);
+                }
+                self.apply_effects();
+
+                if self.balls.is_empty()
+                {
+                    // the last ball is gone, lose a life
+                    self.data.lives -= 1;
+                    if self.data.lives == 0
+                    {
+                        return Some(Outcome::Lose)
+                    }
+                    // capsules, effects and the combo don't carry over to the next ball
+                    self.data.combo = 0;
+                    self.capsules.clear();
+                    self.data.effects.clear();
+                    self.apply_effects();
+                    // go back to the shooting phase
+                    self.phase = Phase::Shoot([0.0, -1.0])
+                }
+                // the points for this step's hits are multiplied by the combo
+                // before them, and touching the paddle starts the combo over
+                self.data.score.bricks += points * self.multiplier();
+                self.data.combo += bricks_hit;
+                self.data.score.best_combo = u32::max(self.data.score.best_combo, self.data.combo);
+                if paddle_hits > 0
+                {
+                    self.data.combo = 0;
+                }
+
+                if bricks_hit > 0
+                {
+                    // up the progress, the balls can hit more than one brick in a step.
+                    // regenerating bricks can take more hits than the progress counted
+                    // on, so it stops at full and the level is over when the bricks
+                    // are gone
+                    self.data.hits = u32::min(self.data.hits + bricks_hit, self.data.max_hits);
+                    if self.bricks.cleared()
+                    {
+                        self.clear_bonus();
+                        return Some(Outcome::Clear)
+                    }
+                }
+            },
+        }
+
+        None
+    }
+
+    // the time left, or under par, and the lives left are worth points at the end
+    fn clear_bonus(&mut self)
+    {
+        let seconds = match (self.data.time_left, self.rules.par)
+        {
+            (Some(left), _) => left / TICKS_PER_SECOND,
+            (None, Some(par)) => par.saturating_sub(self.data.timer),
+            (None, None) => 0,
+        };
+
+        self.data.score.time_bonus += seconds * TIME_POINTS;
+        self.data.score.lives_bonus += self.data.lives * LIFE_POINTS;
+    }
+
+    // the combo multiplier
+    pub fn multiplier(&self) -> u32
+    {
+        u32::min(1 + self.data.combo / COMBO_STEP, MAX_MULTIPLIER)
+    }
+
+    // confine the angle to between [-1.0, -0.15] to [1.0, -0.15]
+    // (y = 0 is the top of the window)
+    fn clamp_angle(angle: [f32; 2]) -> [f32; 2]
+    {
+        if utils::angle_between(angle, [1.0, 0.0]) <
+            utils::angle_between([1.0, -0.15], [1.0, 0.0])
+        {
+            [1.0, -0.15]
+        }
+        else if utils::angle_between(angle, [-1.0, 0.0]) <
+            utils::angle_between([-1.0, -0.15], [-1.0, 0.0])
+        {
+            [-1.0, -0.15]
+        }
+        else
+        {
+            angle
+        }
+    }
+
+    fn launch(&mut self, angle: [f32; 2])
+    {
+        // the balls start right above the center of the paddle, the middle one has
+        // the angle of the current shooter angle and the rest fan out around it
+        let pos =
+        [
+            self.paddle.rect().x + self.paddle.rect().w / 2.0,
+            self.paddle.rect().y + ball::BALL_SIZE
+        ];
+        let middle = (self.rules.balls - 1) as f32 / 2.0;
+
+        self.balls = (0..self.rules.balls)
+            .map(|i| Ball::new(pos,
+                Sim::clamp_angle(utils::rotate(angle, (i as f32 - middle) * BALL_SPREAD))))
+            .collect();
+
+        // move to the next phase
+        self.phase = Phase::Bounce;
+    }
+
+    // a broken brick drops its own capsule, or maybe one of the level's
+    fn drop_capsule(&mut self, brick: &Brick)
+    {
+        let drops = &self.rules.drops;
+        let effect = match brick.drop
+        {
+            Some(effect) if self.rules.allows(effect) => Some(effect),
+            Some(_) => None,
+            None if !drops.effects.is_empty() && self.rng.chance(drops.chance) =>
+            {
+                Some(drops.effects[self.rng.below(drops.effects.len() as u32) as usize])
+            },
+            None => None,
+        };
+
+        if let Some(effect) = effect
+        {
+            self.capsules.push(Capsule::new(brick.center(), effect));
+        }
+    }
+
+    fn catch(&mut self, effect: Effect)
+    {
+        match effect
+        {
+            Effect::Life => self.data.lives = u32::min(self.data.lives + 1, MAX_LIVES),
+            Effect::Time => if let Some(ref mut left) = self.data.time_left
+            {
+                *left += CAPSULE_TIME;
+            },
+            Effect::Multi =>
+            {
+                // the first ball that's moving splits into three
+                let first = self.balls.iter()
+                    .find(|b| !b.stuck())
+                    .or_else(|| self.balls.first())
+                    .cloned();
+                if let Some(ball) = first
+                {
+                    for angle in &[-MULTI_SPREAD, MULTI_SPREAD]
+                    {
+                        if self.balls.len() < MAX_BALLS
+                        {
+                            self.balls.push(ball.split(*angle));
+                        }
+                    }
+                }
+            },
+            _ =>
+            {
+                // the paddle can't be wide and narrow at once, the newest one wins
+                let opposite = match effect
+                {
+                    Effect::Wide => Some(Effect::Narrow),
+                    Effect::Narrow => Some(Effect::Wide),
+                    _ => None,
+                };
+                self.data.effects.retain(|(e, _)| *e != effect && Some(*e) != opposite);
+
+                // catching one that's already going starts it over
+                if let Some(duration) = effect.duration()
+                {
+                    self.data.effects.push((effect, duration));
+                }
+            },
+        }
+    }
+
+    // makes the paddle and balls match the effects that are going
+    fn apply_effects(&mut self)
+    {
+        let width = if self.data.active(Effect::Wide)
+        {
+            paddle::PADDLE_WIDTH * WIDE
+        }
+        else if self.data.active(Effect::Narrow)
+        {
+            paddle::PADDLE_WIDTH * NARROW
+        }
+        else
+        {
+            paddle::PADDLE_WIDTH
+        };
+        if self.paddle.rect().w != width
+        {
+            self.paddle.set_width(width);
+        }
+
+        let speed = if self.data.active(Effect::Slow)
+        {
+            ball::BALL_SPEED * self.rules.speed * SLOW
+        }
+        else
+        {
+            ball::BALL_SPEED * self.rules.speed
+        };
+        let sticky = self.data.active(Effect::Sticky);
+        for ball in self.balls.iter_mut()
+        {
+            ball.set_speed(speed);
+            // once sticky runs out, the stuck balls go
+            if !sticky
+            {
+                ball.release(&self.paddle);
+            }
+        }
+    }
+
+    // a hash of everything that changes during play, replays use it to check that
+    // playing them back gives exactly the same game
+    pub fn checksum(&self) -> u64
+    {
+        let mut c = Checksum::new();
+
+        c.f32(self.paddle.rect().x);
+        c.f32(self.paddle.rect().w);
+        match self.phase
+        {
+            Phase::Shoot(angle) =>
+            {
+                c.u32(0);
+                c.f32(angle[0]);
+                c.f32(angle[1]);
+            },
+            Phase::Bounce => c.u32(1),
+        }
+        c.u32(self.balls.len() as u32);
+        for ball in &self.balls
+        {
+            c.f32(ball.pos()[0]);
+            c.f32(ball.pos()[1]);
+            c.f32(ball.dir()[0]);
+            c.f32(ball.dir()[1]);
+            c.u32(ball.stuck() as u32);
+        }
+        c.u32(self.capsules.len() as u32);
+        for capsule in &self.capsules
+        {
+            c.f32(capsule.rect().x);
+            c.f32(capsule.rect().y);
+            c.u32(capsule.effect().code());
+        }
+        c.u32(self.data.creep);
+        c.u32(self.data.time_left.unwrap_or(0));
+        c.u32(self.data.effects.len() as u32);
+        for (effect, left) in &self.data.effects
+        {
+            c.u32(effect.code());
+            c.u32(*left);
+        }
+        c.u64(self.rng.state());
+        for brick in self.bricks.iter()
+        {
+            c.f32(brick.rect.x);
+            c.f32(brick.rect.y);
+            c.f32(brick.moved[0]);
+            c.f32(brick.moved[1]);
+            c.u32(brick.count);
+            c.u32(brick.kind.code());
+            c.u32(brick.regen);
+            c.u32(brick.visible as u32);
+        }
+        c.u32(self.data.hits);
+        c.u32(self.data.combo);
+        c.u32(self.data.score.total());
+        c.u32(self.data.lives);
+        c.u32(self.data.ticks);
+
+        c.finish()
+    }
+
+    pub fn paddle(&self) -> &Paddle
+    {
+        &self.paddle
+    }
+
+    pub fn balls(&self) -> &[Ball]
+    {
+        &self.balls
+    }
+
+    pub fn bricks(&self) -> &Bricks
+    {
+        &self.bricks
+    }
+
+    pub fn capsules(&self) -> &[Capsule]
+    {
+        &self.capsules
+    }
+
+    // recent explosions, and the steps they're still shown for
+    pub fn explosions(&self) -> &[([f32; 2], u32)]
+    {
+        &self.explosions
+    }
+
+    // the effects that are going, and how many steps they have left
+    pub fn effects(&self) -> &[(Effect, u32)]
+    {
+        &self.data.effects
+    }
+
+    pub fn seed(&self) -> u64
+    {
+        self.seed
+    }
+
+    pub fn creep(&self) -> Option<Creep>
+    {
+        self.rules.creep
+    }
+
+    pub fn time_limit(&self) -> Option<u32>
+    {
+        self.rules.time_limit
+    }
+
+    // the steps left in time attack
+    pub fn time_left(&self) -> Option<u32>
+    {
+        self.data.time_left
+    }
+
+    pub fn phase(&self) -> &Phase
+    {
+        &self.phase
+    }
+
+    pub fn score(&self) -> Score
+    {
+        self.data.score
+    }
+
+    // how many hits have been taken off of the bricks, out of max_hits
+    pub fn hits(&self) -> u32
+    {
+        self.data.hits
+    }
+
+    pub fn max_hits(&self) -> u32
+    {
+        self.data.max_hits
+    }
+
+    pub fn timer(&self) -> u32
+    {
+        self.data.timer
+    }
+
+    pub fn lives(&self) -> u32
+    {
+        self.data.lives
+    }
+}
+
+#[derive(Clone)]
+struct SimData
+{
+    score: Score,
+    // bricks hit without a ball touching the paddle
+    combo: u32,
+    // the progress through the level, in hits on bricks
+    hits: u32,
+    max_hits: u32,
+    // the timer in seconds, and the number of steps it's counted
+    timer: u32,
+    ticks: u32,
+
+    lives: u32,
+    paddle_speed: f32,
+
+    // the effects that are going, and how many steps they have left
+    effects: Vec<(Effect, u32)>,
+    // the steps or paddle hits since the bricks last crept down
+    creep: u32,
+    // the steps left on the clock in time attack
+    time_left: Option<u32>,
+}
+
+impl SimData
+{
+    fn new(max_hits: u32, time_left: Option<u32>) -> SimData
+    {
+        SimData
+        {
+            score: Score::default(),
+            combo: 0,
+            hits: 0,
+            max_hits: max_hits,
+            timer: 0,
+            ticks: 0,
+
+            lives: STARTING_LIVES,
+            paddle_speed: PADDLE_SPEED,
+
+            effects: Vec::new(),
+            creep: 0,
+            time_left: time_left,
+        }
+    }
+
+    fn active(&self, effect: Effect) -> bool
+    {
+        self.effects.iter().any(|(e, _)| *e == effect)
+    }
+}
+
+// the points scored, which are kept apart from how much of the level is done
+#[derive(Copy, Clone, Default)]
+pub struct Score
+{
+    // from hitting bricks, with the combo multiplier
+    pub bricks: u32,
+    // for the time left when the level was cleared
+    pub time_bonus: u32,
+    // for the lives left when the level was cleared
+    pub lives_bonus: u32,
+    // the most bricks hit in one combo
+    pub best_combo: u32,
+}
+
+impl Score
+{
+    pub fn total(&self) -> u32
+    {
+        self.bricks + self.time_bonus + self.lives_bonus
+    }
+}
+
+// how a level plays, apart from its bricks
+#[derive(Clone)]
+pub struct Rules
+{
+    // how many balls are launched at once
+    pub balls: u32,
+    pub drops: Drops,
+    // the bricks move down a row this often
+    pub creep: Option<Creep>,
+    // how fast the balls go, as a multiple of their normal speed
+    pub speed: f32,
+    // the seconds the level has to be cleared in, in time attack
+    pub time_limit: Option<u32>,
+    // clearing the level faster than this many seconds is worth points
+    pub par: Option<u32>,
+}
+
+impl Rules
+{
+    // takes the capsules that can't drop out of the level's drops
+    fn checked(mut self) -> Rules
+    {
+        let mut effects = std::mem::take(&mut self.drops.effects);
+        effects.retain(|e| self.allows(*e));
+        self.drops.effects = effects;
+        self
+    }
+
+    // time capsules do nothing without a time limit, so they don't drop, from the
+    // level's drops or from bricks that always drop them
+    fn allows(&self, effect: Effect) -> bool
+    {
+        effect != Effect::Time || self.time_limit.is_some()
+    }
+
+    fn time_left(&self) -> Option<u32>
+    {
+        self.time_limit.map(|t| t * TICKS_PER_SECOND)
+    }
+}
+
+// how often the bricks move down a row
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Creep
+{
+    Seconds(u32),
+    // times the ball bounces off the paddle
+    Hits(u32),
+}
+
+impl Creep
+{
+    // used when creep mode is on and the level doesn't have its own
+    pub const DEFAULT: Creep = Creep::Seconds(15);
+
+    // how many steps or hits it takes
+    fn steps(self) -> u32
+    {
+        match self
+        {
+            Creep::Seconds(n) => n * TICKS_PER_SECOND,
+            Creep::Hits(n) => n,
+        }
+    }
+
+    // like '20 seconds' or '6 hits'
+    pub fn parse(s: &str) -> Option<Creep>
+    {
+        let v = s.split_whitespace().collect::<Vec<_>>();
+        match v[..]
+        {
+            [n, unit] =>
+            {
+                let n = n.parse::<u32>().ok().filter(|n| *n > 0)?;
+                match unit
+                {
+                    "seconds" => Some(Creep::Seconds(n)),
+                    "hits" => Some(Creep::Hits(n)),
+                    _ => None,
+                }
+            },
+            _ => None,
+        }
+    }
+}
+
+impl std::fmt::Display for Creep
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result
+    {
+        match self
+        {
+            Creep::Seconds(n) => write!(f, "{} seconds", n),
+            Creep::Hits(n) => write!(f, "{} hits", n),
+        }
+    }
+}
+
+// the input the simulation cares about for one step
+#[derive(Copy, Clone, Default, PartialEq, Eq)]
+pub struct InputData
+{
+    // the paddle only moves while the ball is in play
+    pub move_left: bool,
+    pub move_right: bool,
+    // and the shooter only aims before it
+    pub aim_left: bool,
+    pub aim_right: bool,
+    // this is a press rather than a hold, it's only set for the step it happened in
+    pub launch: bool,
+    // the mouse, if it's controlling the paddle
+    pub pointer: Option<Pointer>,
+}
+
+impl InputData
+{
+    // packs the input into a number, for replays. the keys are the low bits, then
+    // whether there's a pointer and how it moves, then its x and y
+    pub fn to_bits(self) -> u32
+    {
+        let mut bits = (self.move_left as u32)
+            | (self.move_right as u32) << 1
+            | (self.launch as u32) << 2
+            | (self.aim_left as u32) << 3
+            | (self.aim_right as u32) << 4;
+
+        if let Some(p) = self.pointer
+        {
+            bits |= 1 << 5 | (p.direct as u32) << 6 | (p.x as u32) << 7 | (p.y as u32) << 18;
+        }
+
+        bits
+    }
+
+    pub fn from_bits(bits: u32) -> InputData
+    {
+        InputData
+        {
+            move_left: bits & 1 != 0,
+            move_right: bits & 2 != 0,
+            launch: bits & 4 != 0,
+            aim_left: bits & 8 != 0,
+            aim_right: bits & 16 != 0,
+            pointer: if bits & 32 != 0
+            {
+                Some(Pointer
+                {
+                    direct: bits & 64 != 0,
+                    x: ((bits >> 7) & 0x7ff) as u16,
+                    y: ((bits >> 18) & 0x7ff) as u16,
+                })
+            }
+            else
+            {
+                None
+            },
+        }
+    }
+}
+
+// where the mouse is, in half pixels so it's the same when it's played back
+#[derive(Copy, Clone, PartialEq, Eq)]
+pub struct Pointer
+{
+    x: u16,
+    y: u16,
+    // the paddle jumps to the mouse, rather than moving towards it at its speed
+    pub direct: bool,
+}
+
+impl Pointer
+{
+    pub fn new(x: f32, y: f32, direct: bool) -> Pointer
+    {
+        let half = |v: f32, max: f32| (v.clamp(0.0, max) * 2.0).round() as u16;
+
+        Pointer
+        {
+            x: half(x, BOARD_WIDTH),
+            y: half(y, BOARD_HEIGHT + FOREHEAD),
+            direct: direct,
+        }
+    }
+
+    pub fn pos(&self) -> [f32; 2]
+    {
+        [self.x as f32 / 2.0, self.y as f32 / 2.0]
+    }
+}
+
+#[derive(Copy, Clone)]
+pub enum Phase
+{
+    Shoot([f32; 2]),
+    Bounce,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Outcome
+{
+    Clear,
+    Lose,
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::levels::Cell;
+    use crate::game::{BrickKind, Motion};
+
+    fn cell(count: u32) -> Cell
+    {
+        Cell
+        {
+            count: count,
+            drop: None,
+            kind: BrickKind::Normal,
+            motion: Motion::Still,
+        }
+    }
+
+    // a level with the given rows, one ball and nothing dropping
+    fn sim(rows: Vec<Vec<Cell>>) -> Sim
+    {
+        let rules = Rules
+        {
+            balls: 1,
+            drops: Drops
+            {
+                chance: 0.0,
+                effects: Vec::new(),
+            },
+            creep: None,
+            speed: 1.0,
+            time_limit: None,
+            par: None,
+        };
+        Sim::new(Bricks::new(Bricks::make_pattern(rows)), rules, 1)
+    }
+
+    fn launch() -> InputData
+    {
+        InputData
+        {
+            launch: true,
+            ..InputData::default()
+        }
+    }
+
+    // runs until the level is over, or gives up after a minute
+    fn run(sim: &mut Sim, input: impl Fn(&Sim) -> InputData) -> Option<Outcome>
+    {
+        (0..60 * TICKS_PER_SECOND).find_map(|_| sim.update(&input(sim)))
+    }
+
+    #[test]
+    fn launched_ball_moves_straight_up_at_its_speed()
+    {
+        let mut sim = sim(Vec::new());
+        assert_eq!(sim.update(&launch()), None);
+        assert!(matches!(sim.phase(), Phase::Bounce));
+
+        // it starts out in the paddle, so give it a moment to get clear of it
+        for _ in 0..20
+        {
+            sim.update(&InputData::default());
+        }
+
+        let start = sim.balls()[0].pos();
+        for _ in 0..10
+        {
+            sim.update(&InputData::default());
+        }
+        let end = sim.balls()[0].pos();
+
+        assert_eq!(end[0], start[0]);
+        assert!((start[1] - end[1] - 10.0 * ball::BALL_SPEED * DT).abs() < 0.01);
+    }
+
+    #[test]
+    fn a_hit_takes_one_off_the_brick_and_bounces_the_ball()
+    {
+        // one brick in the middle of the top row, right above the paddle
+        let mut sim = sim(vec![vec![cell(2)]]);
+        sim.update(&launch());
+
+        let hit = (0..10 * TICKS_PER_SECOND).find(|_|
+        {
+            sim.update(&InputData::default());
+            sim.hits() > 0
+        });
+
+        assert!(hit.is_some());
+        assert_eq!(sim.hits(), 1);
+        assert_eq!(sim.max_hits(), 2);
+        assert_eq!(sim.bricks().iter().next().map(|b| b.count), Some(1));
+        assert!(sim.balls()[0].dir()[1] > 0.0);
+        assert_eq!(sim.score().bricks, 10);
+    }
+
+    #[test]
+    fn breaking_the_last_brick_clears_the_level()
+    {
+        let mut sim = sim(vec![vec![cell(1)]]);
+        assert_eq!(run(&mut sim, |_| launch()), Some(Outcome::Clear));
+        assert!(sim.bricks().cleared());
+        assert_eq!(sim.lives(), STARTING_LIVES);
+    }
+
+    #[test]
+    fn healing_bricks_dont_take_the_progress_past_full()
+    {
+        let regen = Cell
+        {
+            kind: BrickKind::Regen,
+            ..cell(2)
+        };
+
+        let mut sim = sim(vec![vec![regen]]);
+        for _ in 0..20 * TICKS_PER_SECOND
+        {
+            sim.update(&launch());
+            // heal it as soon as it's hit, so it's never broken
+            for b in sim.bricks.bricks().iter_mut().flatten()
+            {
+                b.regen = u32::min(b.regen, 1);
+            }
+        }
+
+        assert!(sim.score().bricks > 2 * BrickKind::Regen.points());
+        assert_eq!(sim.hits(), sim.max_hits());
+    }
+
+    // the sim with a time limit in seconds, as if the level had started with it
+    fn with_time_limit(mut sim: Sim, seconds: u32) -> Sim
+    {
+        sim.rules.time_limit = Some(seconds);
+        sim.data.time_left = sim.rules.time_left();
+        sim
+    }
+
+    #[test]
+    fn running_out_of_time_loses_the_level()
+    {
+        let mut sim = with_time_limit(sim(vec![vec![cell(1)]]), 1);
+        // the clock doesn't run before the launch
+        assert_eq!(sim.update(&InputData::default()), None);
+        assert_eq!(sim.time_left(), Some(TICKS_PER_SECOND));
+
+        assert_eq!(sim.update(&launch()), None);
+        for _ in 1..TICKS_PER_SECOND
+        {
+            assert_eq!(sim.update(&InputData::default()), None);
+        }
+        assert_eq!(sim.time_left(), Some(1));
+
+        assert_eq!(sim.update(&InputData::default()), Some(Outcome::Lose));
+        assert_eq!(sim.time_left(), Some(0));
+        assert_eq!(sim.lives(), STARTING_LIVES);
+    }
+
+    #[test]
+    fn breaking_a_brick_or_catching_a_capsule_adds_time()
+    {
+        let mut sim = with_time_limit(sim(vec![vec![cell(1), cell(1)]]), 60);
+        sim.update(&launch());
+
+        let mut before = sim.time_left();
+        let broke = (0..10 * TICKS_PER_SECOND).find(|_|
+        {
+            before = sim.time_left();
+            sim.update(&InputData::default());
+            sim.hits() > 0
+        });
+
+        assert!(broke.is_some());
+        assert_eq!(sim.time_left(), before.map(|t| t - 1 + BRICK_TIME));
+
+        let before = sim.time_left();
+        sim.catch(Effect::Time);
+        assert_eq!(sim.time_left(), before.map(|t| t + CAPSULE_TIME));
+    }
+
+    #[test]
+    fn a_brick_only_drops_time_with_a_time_limit()
+    {
+        let time = Cell
+        {
+            drop: Some(Effect::Time),
+            ..cell(1)
+        };
+
+        let mut sim = sim(vec![vec![time]]);
+        let brick = *sim.bricks().iter().next().unwrap();
+        sim.drop_capsule(&brick);
+        assert!(sim.capsules().is_empty());
+
+        sim.rules.time_limit = Some(60);
+        sim.drop_capsule(&brick);
+        assert_eq!(sim.capsules().iter().map(|c| c.effect()).collect::<Vec<_>>(), vec![Effect::Time]);
+    }
+
+    #[test]
+    fn missing_every_ball_loses_the_level()
+    {
+        // the paddle gets out of the way of every ball, and there are no bricks to
+        // clear by accident
+        let mut sim = sim(Vec::new());
+        let dodge = |sim: &Sim|
+        {
+            // to the other half of the board
+            let right = sim.balls().first().is_some_and(|b| b.pos()[0] < BOARD_WIDTH / 2.0);
+            InputData
+            {
+                launch: true,
+                move_left: !right,
+                move_right: right,
+                ..InputData::default()
+            }
+        };
+
+        assert_eq!(run(&mut sim, dodge), Some(Outcome::Lose));
+        assert_eq!(sim.lives(), 0);
+    }
+
+    #[test]
+    fn a_pointer_packs_into_the_bits_and_back()
+    {
+        // the corners, the bottom of the window, and somewhere in half pixels
+        let points = [
+            (0.0, 0.0),
+            (BOARD_WIDTH, BOARD_HEIGHT),
+            (BOARD_WIDTH, BOARD_HEIGHT + FOREHEAD),
+            (123.5, 456.5),
+        ];
+        for &(x, y) in points.iter()
+        {
+            let input = InputData
+            {
+                pointer: Some(Pointer::new(x, y, true)),
+                ..InputData::default()
+            };
+            let bits = input.to_bits();
+            // none of the keys, even with the pointer at its furthest
+            assert_eq!(bits & 0x1f, 0, "({}, {})", x, y);
+
+            let back = InputData::from_bits(bits);
+            assert!(back == input, "({}, {})", x, y);
+            assert_eq!(back.pointer.map(|p| p.pos()), Some([x, y]));
+
+            // and the keys don't spill into the pointer
+            let keys = InputData
+            {
+                move_left: true,
+                move_right: true,
+                aim_left: true,
+                aim_right: true,
+                launch: true,
+                ..input
+            };
+            assert!(InputData::from_bits(keys.to_bits()) == keys, "({}, {})", x, y);
+        }
+    }
+
+    #[test]
+    fn the_aim_keys_turn_the_shooter_even_with_the_mouse_on()
+    {
+        let mut sim = sim(vec![vec![cell(1)]]);
+        // the mouse straight above the paddle points the shooter straight up
+        let paddle = sim.paddle.rect();
+        let pointer = Some(Pointer::new(paddle.x + paddle.w / 2.0, paddle.y - 100.0, false));
+        let aim = InputData
+        {
+            aim_left: true,
+            pointer: pointer,
+            ..InputData::default()
+        };
+
+        let mut expected = [0.0, -1.0];
+        for _ in 0..TICKS_PER_SECOND / 4
+        {
+            assert_eq!(sim.update(&aim), None);
+            expected = Sim::clamp_angle(utils::rotate(expected, -ANGLE_CHANGE * DT));
+        }
+        match *sim.phase()
+        {
+            Phase::Shoot(angle) => assert!((angle[0] - expected[0]).abs() < 0.001
+                && (angle[1] - expected[1]).abs() < 0.001),
+            _ => panic!("the ball was launched"),
+        }
+
+        // once they're let go the mouse points it again
+        let still = InputData
+        {
+            pointer: pointer,
+            ..InputData::default()
+        };
+        assert_eq!(sim.update(&still), None);
+        match *sim.phase()
+        {
+            Phase::Shoot(angle) => assert!(angle[0].abs() < 0.001),
+            _ => panic!("the ball was launched"),
+        }
+    }
+}
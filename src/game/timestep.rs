@@ -0,0 +1,77 @@
+use super::sim::{DT, TICKS_PER_SECOND};
+
+// if updates are this many steps apart, the game slows down instead of trying to
+// catch up all at once
+const MAX_STEPS: u32 = TICKS_PER_SECOND / 4;
+
+// runs the simulation at a fixed rate, however often the game is updated
+#[derive(Default)]
+pub struct Timestep
+{
+    // the time that hasn't been simulated yet, in seconds
+    accumulator: f32,
+}
+
+impl Timestep
+{
+    // adds the seconds since the last update, and gives how many steps to run for
+    // them. the time left over is less than a step, and it waits for the next update
+    pub fn advance(&mut self, seconds: f32) -> u32
+    {
+        self.accumulator += seconds;
+        let steps = (self.accumulator / DT) as u32;
+        self.accumulator = f32::max(self.accumulator - steps as f32 * DT, 0.0);
+
+        u32::min(steps, MAX_STEPS)
+    }
+
+    // how far it is between the last step and the next one, from 0 to 1
+    pub fn alpha(&self) -> f32
+    {
+        self.accumulator / DT
+    }
+
+    pub fn reset(&mut self)
+    {
+        self.accumulator = 0.0;
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    #[test]
+    fn a_long_frame_runs_every_step_in_it()
+    {
+        let mut timestep = Timestep::default();
+
+        assert_eq!(timestep.advance(5.5 * DT), 5);
+        assert!((timestep.alpha() - 0.5).abs() < 0.001);
+
+        // the half step left over makes a whole one with the next bit
+        assert_eq!(timestep.advance(0.75 * DT), 1);
+        assert!((timestep.alpha() - 0.25).abs() < 0.001);
+    }
+
+    #[test]
+    fn a_short_frame_waits_for_the_next_one()
+    {
+        let mut timestep = Timestep::default();
+
+        assert_eq!(timestep.advance(0.25 * DT), 0);
+        assert!((timestep.alpha() - 0.25).abs() < 0.001);
+    }
+
+    #[test]
+    fn a_very_long_frame_only_catches_up_so_far()
+    {
+        let mut timestep = Timestep::default();
+
+        assert_eq!(timestep.advance(10.0 + 0.25 * DT), MAX_STEPS);
+        // the steps past the most are dropped rather than saved up for later
+        assert!((timestep.alpha() - 0.25).abs() < 0.01);
+        assert_eq!(timestep.advance(DT), 1);
+    }
+}
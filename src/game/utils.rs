@@ -5,6 +5,12 @@ pub fn len(v: [f32; 2]) -> f32
     f32::sqrt(f32::powi(v[0], 2) + f32::powi(v[1], 2))
 }
 
+// add vector to vector
+pub fn add(l: [f32; 2], r: [f32; 2]) -> [f32; 2]
+{
+    [l[0] + r[0], l[1] + r[1]]
+}
+
 // subtract vector from vector
 pub fn sub(l: [f32; 2], r: [f32; 2]) -> [f32; 2]
 {
@@ -43,127 +49,140 @@ pub fn bounce_angle(v: [f32; 2], n: [f32; 2]) -> [f32; 2]
     sub(mul(mul(n, dot(n, v)), 2.0), v)
 }
 
-#[derive(Copy, Clone, Debug, PartialEq, Eq)]
-pub enum Axis
+// reflect a vector off of a surface with the normal n
+pub fn reflect(v: [f32; 2], n: [f32; 2]) -> [f32; 2]
 {
-    X,
-    Y,
+    // v - 2(v . n)n
+    sub(v, mul(n, dot(v, n) * 2.0))
 }
 
-// shortcut when the bounce is off of an axis
-pub fn bounce_axis(v: [f32; 2], axis: Axis) -> [f32; 2]
+// where a moving circle first touches something
+#[derive(Copy, Clone, Debug)]
+pub struct Hit
 {
-    match axis
-    {
-        Axis::X =>
-        {
-            [v[0], -v[1]]
-        },
-        Axis::Y =>
-        {
-            [-v[0], v[1]]
-        },
-    }
+    // the time of impact, as a fraction of the movement
+    pub t: f32,
+    // the normal of the surface that was hit, pointing at the circle
+    pub normal: [f32; 2],
 }
 
-// o: old position, p: projected position, s: size, r: rect
-pub fn intersect_rect(o: [f32; 2], p: [f32; 2], s: f32, r: ggez::graphics::Rect) -> Option<Axis>
+// the first time the ray p + t * d (with t in [0, 1]) is within s of the point c
+fn sweep_point(p: [f32; 2], d: [f32; 2], s: f32, c: [f32; 2]) -> Option<Hit>
 {
-    if r.contains(p)
+    let m = sub(p, c);
+    let a = dot(d, d);
+    let b = 2.0 * dot(m, d);
+    let c2 = dot(m, m) - s * s;
+
+    let disc = b * b - 4.0 * a * c2;
+    if a == 0.0 || disc < 0.0
     {
-        // distance to either x face
-        let xdist = f32::min(f32::abs(o[1] - r.top()), f32::abs(o[1] - r.bottom()));
-        // distance to either y face
-        let ydist = f32::min(f32::abs(o[0] - r.left()), f32::abs(o[0] - r.right()));
+        return None
+    }
 
-        if xdist < ydist
-        {
-            return Some(Axis::X)
-        }
-        else
+    let t = (-b - f32::sqrt(disc)) / (2.0 * a);
+    if (0.0..=1.0).contains(&t)
+    {
+        let at = [p[0] + d[0] * t, p[1] + d[1] * t];
+        Some(Hit
         {
-            return Some(Axis::Y)
-        }
+            t: t,
+            normal: normalize(sub(at, c)),
+        })
     }
     else
     {
-        if p[0] > r.left() && p[0] < r.right()
-        {
-            if f32::abs(p[1] - r.top()) < s ||
-                f32::abs(p[1] - r.bottom()) < s
-            {
-                return Some(Axis::X)
-            }
-        }
-        if p[1] > r.top() && p[1] < r.bottom()
+        None
+    }
+}
+
+// sweeps a circle with center p and radius s along d (ending up at p + d), and
+// finds the first time it touches the rect r.
+// the circle touching the rect is the same as its center touching the rect grown
+// by s, which is the rect grown sideways, the rect grown up and down, and circles on
+// the corners, so the earliest of those is the hit.
+// if the circle already overlaps the rect and is moving further in, it's a hit at t = 0
+pub fn sweep_circle_rect(p: [f32; 2], d: [f32; 2], s: f32, r: ggez::graphics::Rect) -> Option<Hit>
+{
+    // already overlapping
+    let closest = [p[0].max(r.left()).min(r.right()), p[1].max(r.top()).min(r.bottom())];
+    if len(sub(p, closest)) < s
+    {
+        let normal = if p == closest
         {
-            if f32::abs(p[0] - r.left()) < s ||
-                f32::abs(p[0] - r.right()) < s
-            {
-                return Some(Axis::Y)
-            }
-        }
+            // the center is inside the rect, so push it out the nearest side
+            let dleft = p[0] - r.left();
+            let dright = r.right() - p[0];
+            let dtop = p[1] - r.top();
+            let dbottom = r.bottom() - p[1];
+            let min = dleft.min(dright).min(dtop).min(dbottom);
 
-        let corner_intersect = |c: [f32; 2]|
+            if min == dleft { [-1.0, 0.0] }
+            else if min == dright { [1.0, 0.0] }
+            else if min == dtop { [0.0, -1.0] }
+            else { [0.0, 1.0] }
+        }
+        else
         {
-            f32::sqrt(f32::powi(p[0] - c[0], 2) + f32::powi(p[1] - c[1], 2)) < s
+            normalize(sub(p, closest))
         };
-        
-        if corner_intersect([r.left(), r.top()])
+
+        if dot(d, normal) < 0.0
         {
-            if f32::abs(p[0] - r.left()) < f32::abs(p[1] - r.top())
-            {
-                return Some(Axis::X)
-            }
-            else
+            return Some(Hit
             {
-                return Some(Axis::Y)
-            }
+                t: 0.0,
+                normal: normal,
+            })
         }
-        if corner_intersect([r.left(), r.bottom()])
+        return None
+    }
+
+    let mut best: Option<Hit> = None;
+    let mut consider = |hit: Option<Hit>|
+    {
+        if let Some(hit) = hit
         {
-            if f32::abs(p[0] - r.left()) < f32::abs(p[1] - r.bottom())
-            {
-                return Some(Axis::X)
-            }
-            else
+            if best.map_or(true, |b| hit.t < b.t)
             {
-                return Some(Axis::Y)
+                best = Some(hit);
             }
         }
-        if corner_intersect([r.right(), r.top()])
+    };
+
+    // the sides of the rect grown sideways
+    if d[0] != 0.0
+    {
+        let (x, n) = if d[0] > 0.0 { (r.left() - s, -1.0) } else { (r.right() + s, 1.0) };
+        let t = (x - p[0]) / d[0];
+        let y = p[1] + d[1] * t;
+        if (0.0..=1.0).contains(&t) && y >= r.top() && y <= r.bottom()
         {
-            if f32::abs(p[0] - r.right()) < f32::abs(p[1] - r.top())
-            {
-                return Some(Axis::X)
-            }
-            else
-            {
-                return Some(Axis::Y)
-            }
+            consider(Some(Hit { t: t, normal: [n, 0.0] }));
         }
-        if corner_intersect([r.right(), r.bottom()])
+    }
+    // the top and bottom of the rect grown up and down
+    if d[1] != 0.0
+    {
+        let (y, n) = if d[1] > 0.0 { (r.top() - s, -1.0) } else { (r.bottom() + s, 1.0) };
+        let t = (y - p[1]) / d[1];
+        let x = p[0] + d[0] * t;
+        if (0.0..=1.0).contains(&t) && x >= r.left() && x <= r.right()
         {
-            if f32::abs(p[0] - r.right()) < f32::abs(p[1] - r.bottom())
-            {
-                return Some(Axis::X)
-            }
-            else
-            {
-                return Some(Axis::Y)
-            }
+            consider(Some(Hit { t: t, normal: [0.0, n] }));
         }
     }
+    // the corners
+    for c in &[
+        [r.left(), r.top()],
+        [r.right(), r.top()],
+        [r.left(), r.bottom()],
+        [r.right(), r.bottom()]]
+    {
+        consider(sweep_point(p, d, s, *c));
+    }
 
-    None
-}
-
-// distance to the center of the rectangle
-pub fn dist_to_rect(p: [f32; 2], r: ggez::graphics::Rect) -> f32
-{
-    let c = [r.x + r.w / 2.0, r.y + r.h / 2.0];
-
-    f32::sqrt(f32::powi(p[0] - c[0], 2) + f32::powi(p[1] - c[1], 2))
+    best
 }
 
 // if the point is inside the rect, returns the closest point that's outside the rect (with the size)
@@ -213,3 +232,61 @@ pub fn angle_between(v: [f32; 2], n: [f32; 2]) -> f32
 {
     f32::acos(dot(v, n) / (len(v) * len(n)))
 }
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use ggez::graphics::Rect;
+
+    fn close(a: [f32; 2], b: [f32; 2]) -> bool
+    {
+        len(sub(a, b)) < 0.001
+    }
+
+    #[test]
+    fn a_fast_circle_still_hits_a_thin_rect()
+    {
+        // it would be well past the rect after the move
+        let r = Rect::new(0.0, 40.0, 100.0, 2.0);
+        let hit = sweep_circle_rect([50.0, 100.0], [0.0, -100.0], 5.0, r).unwrap();
+
+        // touching the bottom, at y = 42 + 5
+        assert!((hit.t - 0.53).abs() < 0.001);
+        assert_eq!(hit.normal, [0.0, 1.0]);
+    }
+
+    #[test]
+    fn a_circle_going_past_a_corner_hits_the_corner()
+    {
+        let r = Rect::new(0.0, 0.0, 10.0, 10.0);
+        let hit = sweep_circle_rect([-10.0, -10.0], [10.0, 10.0], 1.0, r).unwrap();
+
+        let at = [-10.0 + 10.0 * hit.t, -10.0 + 10.0 * hit.t];
+        assert!((len(at) - 1.0).abs() < 0.001);
+        assert!(close(hit.normal, normalize([-1.0, -1.0])));
+    }
+
+    #[test]
+    fn a_circle_that_misses_or_moves_away_doesnt_hit()
+    {
+        let r = Rect::new(0.0, 0.0, 10.0, 10.0);
+        // past the corner, just out of reach
+        assert!(sweep_circle_rect([-10.0, -2.0], [30.0, 0.0], 1.0, r).is_none());
+        // overlapping, but already on its way out
+        assert!(sweep_circle_rect([5.0, -0.5], [0.0, -5.0], 1.0, r).is_none());
+        // overlapping and going further in is a hit straight away
+        let hit = sweep_circle_rect([5.0, -0.5], [0.0, 5.0], 1.0, r).unwrap();
+        assert_eq!(hit.t, 0.0);
+        assert_eq!(hit.normal, [0.0, -1.0]);
+    }
+
+    #[test]
+    fn sweep_point_finds_the_first_touch()
+    {
+        let hit = sweep_point([0.0, 0.0], [10.0, 0.0], 1.0, [5.0, 0.0]).unwrap();
+        assert!((hit.t - 0.4).abs() < 0.001);
+        assert!(close(hit.normal, [-1.0, 0.0]));
+        assert!(sweep_point([0.0, 0.0], [3.0, 0.0], 1.0, [5.0, 0.0]).is_none());
+    }
+}
@@ -0,0 +1,272 @@
+use ggez::
+{
+    Context,
+    GameResult,
+    graphics::Color,
+    input::
+    {
+        keyboard::KeyCode,
+        mouse::MouseButton,
+    },
+};
+
+use crate::BOARD_WIDTH;
+use crate::
+{
+    state::State,
+    menu::Menu,
+    daily,
+    scores::{Table, TABLE_SIZE},
+    shared::Shared,
+    ui::{TextRect, Button, focus::{self, FocusKey}},
+};
+
+const BORDER: f32 = 25.0;
+const BUTTON_BORDER: f32 = 15.0;
+const SIZE: f32 = 35.0;
+
+const NAME_SIZE: f32 = 20.0;
+const HEADER_SIZE: f32 = 14.0;
+const ROW_SIZE: f32 = 20.0;
+const ROW_SEP: f32 = 6.0;
+
+// where the columns are, the rank and name are lined up on their left, and the score
+// and time on their right
+const RANK_X: f32 = 40.0;
+const NAME_X: f32 = 85.0;
+const SCORE_X: f32 = 360.0;
+const TIME_X: f32 = 460.0;
+
+// the top ten scores for each level and mode, one table at a time
+pub struct HighScores
+{
+    title: TextRect,
+    name: TextRect,
+    header: Vec<TextRect>,
+    rows: Vec<TextRect>,
+
+    prev: Button,
+    next: Button,
+    back: Button,
+
+    tables: Vec<Table>,
+    page: usize,
+    // the top of the rows
+    top: f32,
+    // a score that was just put in, shown in a different color
+    highlight: Option<(Table, usize)>,
+    // where back goes, the main menu if there's nothing else
+    back_to: Option<Box<State>>,
+}
+
+impl HighScores
+{
+    // it starts on table if it's given, or the first level's otherwise
+    pub fn new(
+        ctx: &mut Context,
+        shared: &Shared,
+        table: Option<Table>,
+        highlight: Option<usize>,
+        back_to: Option<Box<State>>)
+        -> GameResult<HighScores>
+    {
+        let mut tables = shared.scores.tables(&shared.levels);
+        // without any levels or scores there's still today's challenge to show
+        if tables.is_empty()
+        {
+            tables.push(Table::Daily(daily::today()));
+        }
+
+        let page = table.as_ref()
+            .and_then(|t| tables.iter().position(|u| u == t))
+            .unwrap_or(0);
+
+        let title = TextRect::new(ctx, 0.0, BORDER, "High Scores", None, SIZE, None)
+            .center_x(BOARD_WIDTH);
+
+        let name = TextRect::new(ctx, 0.0, title.rect().bottom() + ROW_SEP,
+            tables[page].name(&shared.levels), None, NAME_SIZE, None)
+            .center_x(BOARD_WIDTH);
+
+        let header_y = name.rect().bottom() + BORDER;
+        let header = HighScores::make_row(ctx, header_y, ["#", "name", "score", "time"],
+            HEADER_SIZE, None);
+
+        let top = header.iter().map(|t| t.rect().bottom()).fold(header_y, f32::max) + ROW_SEP;
+
+        // the buttons below the rows are always in the same place, as if the table were full
+        let row_h = TextRect::new(ctx, 0.0, 0.0, "0", None, ROW_SIZE, None).rect().h;
+        let bottom = top + TABLE_SIZE as f32 * row_h + (TABLE_SIZE - 1) as f32 * ROW_SEP;
+
+        let back_text = TextRect::new(ctx,
+            0.0, bottom + BORDER,
+            "Back", None, SIZE, None)
+            .center_x(BOARD_WIDTH);
+        let back = Button::new(ctx, back_text, BUTTON_BORDER)?;
+
+        let prev_text = TextRect::new(ctx,
+            BORDER + BUTTON_BORDER, bottom + BORDER,
+            "<", None, SIZE, None);
+        let prev = Button::new(ctx, prev_text, BUTTON_BORDER)?;
+
+        let mut next_text = TextRect::new(ctx,
+            0.0, bottom + BORDER,
+            ">", None, SIZE, None);
+        next_text.move_to(
+            BOARD_WIDTH - BORDER - BUTTON_BORDER - next_text.rect().w,
+            next_text.rect().y);
+        let next = Button::new(ctx, next_text, BUTTON_BORDER)?;
+
+        let highlight = highlight.map(|rank| (tables[page].clone(), rank));
+
+        let mut high_scores = HighScores
+        {
+            title: title,
+            name: name,
+            header: header,
+            rows: Vec::new(),
+
+            prev: prev,
+            next: next,
+            back: back,
+
+            tables: tables,
+            page: page,
+            top: top,
+            highlight: highlight,
+            back_to: back_to,
+        };
+        high_scores.set_page(ctx, shared, page);
+
+        Ok(high_scores)
+    }
+
+    // the four columns of a row
+    fn make_row(ctx: &mut Context, y: f32, columns: [&str; 4], size: f32, color: Option<Color>)
+        -> Vec<TextRect>
+    {
+        let mut row = Vec::new();
+        for (i, (text, x)) in columns.iter().zip(&[RANK_X, NAME_X, SCORE_X, TIME_X]).enumerate()
+        {
+            let mut t = TextRect::new(ctx, *x, y, text, None, size, color);
+            if i >= 2
+            {
+                let w = t.rect().w;
+                t.move_to(x - w, y);
+            }
+            row.push(t);
+        }
+        row
+    }
+
+    fn set_page(&mut self, ctx: &mut Context, shared: &Shared, page: usize)
+    {
+        self.page = page;
+        let table = &self.tables[page];
+
+        // keep the name centered
+        self.name.change_text(ctx, table.name(&shared.levels));
+        let name = self.name.rect();
+        self.name.move_to(BOARD_WIDTH / 2.0 - name.w / 2.0, name.y);
+
+        self.rows.clear();
+        let mut y = self.top;
+        let entries = shared.scores.table(table);
+        if entries.is_empty()
+        {
+            self.rows.push(TextRect::new(ctx, 0.0, y, "no scores yet", None, ROW_SIZE, None)
+                .center_x(BOARD_WIDTH));
+        }
+        for (i, e) in entries.iter().enumerate()
+        {
+            let color = match self.highlight
+            {
+                Some((ref t, rank)) if t == table && rank == i => Some(Color::from_rgb(0, 120, 220)),
+                _ => None,
+            };
+
+            let row = HighScores::make_row(ctx, y,
+                [&format!("{}.", i + 1), &e.name, &e.score.to_string(), &e.time.to_string()],
+                ROW_SIZE, color);
+            y = row.iter().map(|t| t.rect().bottom()).fold(y, f32::max) + ROW_SEP;
+            self.rows.extend(row);
+        }
+    }
+
+    pub fn update(&mut self, ctx: &mut Context, shared: &mut Shared) -> GameResult<Option<State>>
+    {
+        // the page buttons are reset after use, so holding them down only changes
+        // the page once
+        if self.prev.click()
+        {
+            self.prev.reset();
+            let page = if self.page > 0 { self.page - 1 } else { self.tables.len() - 1 };
+            self.set_page(ctx, shared, page);
+        }
+        if self.next.click()
+        {
+            self.next.reset();
+            self.set_page(ctx, shared, (self.page + 1) % self.tables.len());
+        }
+
+        if self.back.click()
+        {
+            return Ok(Some(match self.back_to.take()
+            {
+                Some(state) => *state,
+                None => State::Menu(Menu::new(ctx)?),
+            }))
+        }
+
+        Ok(None)
+    }
+
+    // escape goes back, wherever back goes
+    pub fn key_down(&mut self, key: KeyCode, repeat: bool)
+    {
+        if focus::key_down(&mut [&mut self.prev, &mut self.next, &mut self.back], key, repeat)
+            == FocusKey::Back
+        {
+            self.back.press();
+        }
+    }
+
+    pub fn key_up(&mut self, key: KeyCode)
+    {
+        focus::key_up(&mut [&mut self.prev, &mut self.next, &mut self.back], key);
+    }
+
+    pub fn mouse_move(&mut self, x: f32, y: f32)
+    {
+        self.prev.mouse_move(x, y);
+        self.next.mouse_move(x, y);
+        self.back.mouse_move(x, y);
+    }
+
+    pub fn mouse_down(&mut self, button: MouseButton, x: f32, y: f32)
+    {
+        self.prev.mouse_down(button, x, y);
+        self.next.mouse_down(button, x, y);
+        self.back.mouse_down(button, x, y);
+    }
+
+    pub fn mouse_up(&mut self, button: MouseButton)
+    {
+        self.prev.mouse_up(button);
+        self.next.mouse_up(button);
+        self.back.mouse_up(button);
+    }
+
+    pub fn draw(&self, ctx: &mut Context) -> GameResult<()>
+    {
+        self.title.draw(ctx)?;
+        self.name.draw(ctx)?;
+        for t in self.header.iter().chain(&self.rows)
+        {
+            t.draw(ctx)?;
+        }
+        self.prev.draw(ctx)?;
+        self.next.draw(ctx)?;
+        self.back.draw(ctx)
+    }
+}
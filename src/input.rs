@@ -0,0 +1,548 @@
+use ggez::input::keyboard::KeyCode;
+use ggez::input::mouse::MouseButton;
+use ggez::event::{Axis, Button};
+
+use std::collections::HashSet;
+
+// how far a stick has to be pushed before it counts
+const DEADZONE: f32 = 0.5;
+
+// the things the player can do in the game, keys are bound to these rather than
+// the game looking at keys itself
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Action
+{
+    MoveLeft,
+    MoveRight,
+    Launch,
+    Pause,
+    AimLeft,
+    AimRight,
+}
+
+impl Action
+{
+    pub const ALL: [Action; 6] = [
+        Action::MoveLeft,
+        Action::MoveRight,
+        Action::Launch,
+        Action::Pause,
+        Action::AimLeft,
+        Action::AimRight,
+    ];
+
+    // the name in the settings file
+    pub fn name(self) -> &'static str
+    {
+        match self
+        {
+            Action::MoveLeft => "move_left",
+            Action::MoveRight => "move_right",
+            Action::Launch => "launch",
+            Action::Pause => "pause",
+            Action::AimLeft => "aim_left",
+            Action::AimRight => "aim_right",
+        }
+    }
+
+    // the name on the controls screen
+    pub fn label(self) -> &'static str
+    {
+        match self
+        {
+            Action::MoveLeft => "Move Left",
+            Action::MoveRight => "Move Right",
+            Action::Launch => "Launch",
+            Action::Pause => "Pause",
+            Action::AimLeft => "Aim Left",
+            Action::AimRight => "Aim Right",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Action>
+    {
+        Action::ALL.iter().copied().find(|a| a.name() == name)
+    }
+
+    fn index(self) -> usize
+    {
+        Action::ALL.iter().position(|a| *a == self).unwrap_or(0)
+    }
+}
+
+// whether the mouse moves the paddle, and how
+#[derive(Copy, Clone, PartialEq, Eq)]
+pub enum MouseControl
+{
+    Off,
+    // the paddle moves towards the cursor at its usual speed
+    Speed,
+    // the paddle is always under the cursor
+    Direct,
+}
+
+impl MouseControl
+{
+    const ALL: [MouseControl; 3] = [MouseControl::Off, MouseControl::Speed, MouseControl::Direct];
+
+    // the name in the settings file
+    pub fn name(self) -> &'static str
+    {
+        match self
+        {
+            MouseControl::Off => "off",
+            MouseControl::Speed => "speed",
+            MouseControl::Direct => "direct",
+        }
+    }
+
+    // the name on the controls screen
+    pub fn label(self) -> &'static str
+    {
+        match self
+        {
+            MouseControl::Off => "off",
+            MouseControl::Speed => "follow",
+            MouseControl::Direct => "direct",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<MouseControl>
+    {
+        MouseControl::ALL.iter().copied().find(|m| m.name() == name)
+    }
+
+    // the next one along, for cycling through them
+    pub fn next(self) -> MouseControl
+    {
+        let i = MouseControl::ALL.iter().position(|m| *m == self).unwrap_or(0);
+        MouseControl::ALL[(i + 1) % MouseControl::ALL.len()]
+    }
+}
+
+// the keys that can be bound, they're written in the settings file by name
+const KEYS: [KeyCode; 62] = [
+    KeyCode::A, KeyCode::B, KeyCode::C, KeyCode::D, KeyCode::E, KeyCode::F, KeyCode::G,
+    KeyCode::H, KeyCode::I, KeyCode::J, KeyCode::K, KeyCode::L, KeyCode::M, KeyCode::N,
+    KeyCode::O, KeyCode::P, KeyCode::Q, KeyCode::R, KeyCode::S, KeyCode::T, KeyCode::U,
+    KeyCode::V, KeyCode::W, KeyCode::X, KeyCode::Y, KeyCode::Z,
+    KeyCode::Key0, KeyCode::Key1, KeyCode::Key2, KeyCode::Key3, KeyCode::Key4,
+    KeyCode::Key5, KeyCode::Key6, KeyCode::Key7, KeyCode::Key8, KeyCode::Key9,
+    KeyCode::Numpad0, KeyCode::Numpad1, KeyCode::Numpad2, KeyCode::Numpad3, KeyCode::Numpad4,
+    KeyCode::Numpad5, KeyCode::Numpad6, KeyCode::Numpad7, KeyCode::Numpad8, KeyCode::Numpad9,
+    KeyCode::Left, KeyCode::Right, KeyCode::Up, KeyCode::Down,
+    KeyCode::Space, KeyCode::Return, KeyCode::Tab,
+    KeyCode::LShift, KeyCode::RShift, KeyCode::LControl, KeyCode::RControl,
+    KeyCode::LAlt, KeyCode::RAlt,
+    KeyCode::Comma, KeyCode::Period, KeyCode::Slash,
+];
+
+pub fn key_name(key: KeyCode) -> String
+{
+    format!("{:?}", key)
+}
+
+pub fn key_from_name(name: &str) -> Option<KeyCode>
+{
+    KEYS.iter().copied().find(|k| key_name(*k) == name)
+}
+
+// whether a key can be bound at all
+pub fn bindable(key: KeyCode) -> bool
+{
+    KEYS.contains(&key)
+}
+
+// which keys do each action, an action can have any number of keys, and a key can
+// do more than one action (the arrows move the paddle and aim by default)
+#[derive(Clone)]
+pub struct Bindings
+{
+    keys: [Vec<KeyCode>; Action::ALL.len()],
+}
+
+impl Default for Bindings
+{
+    fn default() -> Bindings
+    {
+        let mut b = Bindings
+        {
+            keys: Default::default(),
+        };
+
+        b.keys[Action::MoveLeft.index()] = vec![KeyCode::Left, KeyCode::A];
+        b.keys[Action::MoveRight.index()] = vec![KeyCode::Right, KeyCode::D];
+        b.keys[Action::Launch.index()] = vec![KeyCode::Return, KeyCode::Space];
+        b.keys[Action::Pause.index()] = vec![KeyCode::P];
+        b.keys[Action::AimLeft.index()] = vec![KeyCode::Left, KeyCode::A];
+        b.keys[Action::AimRight.index()] = vec![KeyCode::Right, KeyCode::D];
+
+        b
+    }
+}
+
+impl Bindings
+{
+    pub fn keys(&self, action: Action) -> &[KeyCode]
+    {
+        &self.keys[action.index()]
+    }
+
+    pub fn set_keys(&mut self, action: Action, keys: Vec<KeyCode>)
+    {
+        self.keys[action.index()] = keys;
+    }
+
+    // binds the key to the action, or unbinds it if it already was
+    pub fn toggle(&mut self, action: Action, key: KeyCode)
+    {
+        let keys = &mut self.keys[action.index()];
+        match keys.iter().position(|k| *k == key)
+        {
+            Some(i) => { keys.remove(i); },
+            None => keys.push(key),
+        }
+    }
+
+    // the keys for an action as they're shown on the controls screen
+    pub fn describe(&self, action: Action) -> String
+    {
+        if self.keys(action).is_empty()
+        {
+            "(none)".to_string()
+        }
+        else
+        {
+            self.keys(action).iter().map(|k| key_name(*k)).collect::<Vec<_>>().join(", ")
+        }
+    }
+}
+
+// the keys and gamepad buttons being held down, and where the stick is. gamepads
+// aren't rebindable: the d-pad or left stick moves and aims, A launches and
+// start pauses.
+//
+// the actions are looked at once a tick, which gives whether each one is held, and
+// whether it was pressed or released since the tick before. anything pressed
+// between ticks counts as held for one, so a quick tap isn't missed
+#[derive(Default)]
+pub struct Input
+{
+    keys: HashSet<KeyCode>,
+    buttons: HashSet<Button>,
+    stick_x: f32,
+    // the left mouse button
+    mouse: bool,
+
+    // what went down since the last tick
+    tapped_keys: HashSet<KeyCode>,
+    tapped_buttons: HashSet<Button>,
+    tapped_mouse: bool,
+
+    // each action as of the last tick, and the tick before that
+    now: [bool; Action::ALL.len()],
+    before: [bool; Action::ALL.len()],
+}
+
+impl Input
+{
+    // key repeats don't count as new presses
+    pub fn key_down(&mut self, key: KeyCode)
+    {
+        if self.keys.insert(key)
+        {
+            self.tapped_keys.insert(key);
+        }
+    }
+
+    pub fn key_up(&mut self, key: KeyCode)
+    {
+        self.keys.remove(&key);
+    }
+
+    pub fn pad_down(&mut self, button: Button)
+    {
+        if self.buttons.insert(button)
+        {
+            self.tapped_buttons.insert(button);
+        }
+    }
+
+    pub fn pad_up(&mut self, button: Button)
+    {
+        self.buttons.remove(&button);
+    }
+
+    pub fn pad_axis(&mut self, axis: Axis, value: f32)
+    {
+        match axis
+        {
+            Axis::LeftStickX | Axis::DPadX => self.stick_x = value,
+            _ => { },
+        }
+    }
+
+    pub fn mouse_down(&mut self, button: MouseButton)
+    {
+        if button == MouseButton::Left && !self.mouse
+        {
+            self.mouse = true;
+            self.tapped_mouse = true;
+        }
+    }
+
+    pub fn mouse_up(&mut self, button: MouseButton)
+    {
+        if button == MouseButton::Left
+        {
+            self.mouse = false;
+        }
+    }
+
+    pub fn clear(&mut self)
+    {
+        *self = Input::default();
+    }
+
+    // looks at every action again. a left click launches while the mouse is
+    // controlling the paddle
+    pub fn tick(&mut self, bindings: &Bindings, mouse: MouseControl)
+    {
+        for action in Action::ALL.iter()
+        {
+            let i = action.index();
+            self.before[i] = self.now[i];
+            self.now[i] = self.down(bindings, *action)
+                || (*action == Action::Launch && mouse != MouseControl::Off
+                    && (self.mouse || self.tapped_mouse));
+        }
+
+        self.tapped_keys.clear();
+        self.tapped_buttons.clear();
+        self.tapped_mouse = false;
+    }
+
+    // whether any of an action's keys are down, or its part of the gamepad
+    fn down(&self, bindings: &Bindings, action: Action) -> bool
+    {
+        let button = |b| self.buttons.contains(&b) || self.tapped_buttons.contains(&b);
+
+        let pad = match action
+        {
+            Action::MoveLeft | Action::AimLeft =>
+                button(Button::DPadLeft) || self.stick_x < -DEADZONE,
+            Action::MoveRight | Action::AimRight =>
+                button(Button::DPadRight) || self.stick_x > DEADZONE,
+            Action::Launch => button(Button::South),
+            Action::Pause => button(Button::Start),
+        };
+
+        pad || bindings.keys(action).iter()
+            .any(|k| self.keys.contains(k) || self.tapped_keys.contains(k))
+    }
+
+    pub fn held(&self, action: Action) -> bool
+    {
+        self.now[action.index()]
+    }
+
+    // held this tick, but not the one before
+    pub fn pressed(&self, action: Action) -> bool
+    {
+        self.now[action.index()] && !self.before[action.index()]
+    }
+
+    // held the tick before, but not this one
+    pub fn released(&self, action: Action) -> bool
+    {
+        !self.now[action.index()] && self.before[action.index()]
+    }
+}
+
+// outside of the game the gamepad stands in for the keys that get around the menus
+pub fn pad_key(button: Button) -> Option<KeyCode>
+{
+    match button
+    {
+        Button::DPadUp => Some(KeyCode::Up),
+        Button::DPadDown => Some(KeyCode::Down),
+        Button::DPadLeft => Some(KeyCode::Left),
+        Button::DPadRight => Some(KeyCode::Right),
+        Button::South => Some(KeyCode::Return),
+        Button::East => Some(KeyCode::Escape),
+        _ => None,
+    }
+}
+
+// turns the stick into arrow key presses for the menus, a press when it's pushed
+// past the deadzone and a release when it comes back
+#[derive(Default)]
+pub struct StickKeys
+{
+    x: Option<KeyCode>,
+    y: Option<KeyCode>,
+}
+
+impl StickKeys
+{
+    // gives (released, pressed), either can be None
+    pub fn axis(&mut self, axis: Axis, value: f32) -> (Option<KeyCode>, Option<KeyCode>)
+    {
+        let (held, key) = match axis
+        {
+            Axis::LeftStickX | Axis::DPadX => (&mut self.x,
+                if value < -DEADZONE { Some(KeyCode::Left) }
+                else if value > DEADZONE { Some(KeyCode::Right) }
+                else { None }),
+            // up is positive
+            Axis::LeftStickY | Axis::DPadY => (&mut self.y,
+                if value > DEADZONE { Some(KeyCode::Up) }
+                else if value < -DEADZONE { Some(KeyCode::Down) }
+                else { None }),
+            _ => return (None, None),
+        };
+
+        if *held == key
+        {
+            return (None, None)
+        }
+
+        let released = *held;
+        *held = key;
+        (released, key)
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    // a tick with the default keys, and without the mouse
+    fn tick(input: &mut Input)
+    {
+        input.tick(&Bindings::default(), MouseControl::Off);
+    }
+
+    #[test]
+    fn the_stick_moves_once_it_is_past_the_deadzone()
+    {
+        let mut input = Input::default();
+
+        input.pad_axis(Axis::LeftStickX, -DEADZONE * 0.5);
+        tick(&mut input);
+        assert!(!input.held(Action::MoveLeft));
+
+        input.pad_axis(Axis::LeftStickX, -DEADZONE * 1.5);
+        tick(&mut input);
+        assert!(input.held(Action::MoveLeft));
+        assert!(input.held(Action::AimLeft));
+        assert!(!input.held(Action::MoveRight));
+
+        input.pad_axis(Axis::LeftStickX, DEADZONE * 1.5);
+        tick(&mut input);
+        assert!(input.held(Action::MoveRight));
+        assert!(!input.held(Action::MoveLeft));
+
+        input.pad_axis(Axis::LeftStickX, 0.0);
+        tick(&mut input);
+        assert!(!input.held(Action::MoveRight));
+    }
+
+    #[test]
+    fn a_button_tapped_between_ticks_is_held_for_one()
+    {
+        let mut input = Input::default();
+
+        for (button, action) in [(Button::South, Action::Launch), (Button::Start, Action::Pause)]
+        {
+            input.pad_down(button);
+            input.pad_up(button);
+            tick(&mut input);
+            assert!(input.pressed(action));
+
+            tick(&mut input);
+            assert!(!input.held(action));
+        }
+    }
+
+    #[test]
+    fn letting_go_of_a_button_clears_its_action()
+    {
+        let mut input = Input::default();
+
+        input.pad_down(Button::DPadRight);
+        tick(&mut input);
+        tick(&mut input);
+        assert!(input.held(Action::MoveRight));
+
+        input.pad_up(Button::DPadRight);
+        tick(&mut input);
+        assert!(!input.held(Action::MoveRight));
+    }
+
+    #[test]
+    fn the_stick_presses_and_releases_arrow_keys_in_the_menus()
+    {
+        let mut stick = StickKeys::default();
+
+        assert_eq!(stick.axis(Axis::LeftStickX, DEADZONE * 0.5), (None, None));
+        assert_eq!(stick.axis(Axis::LeftStickX, DEADZONE * 1.5), (None, Some(KeyCode::Right)));
+        // staying past it doesn't press it again
+        assert_eq!(stick.axis(Axis::LeftStickX, 1.0), (None, None));
+        // straight across to the other side lets go of one and presses the other
+        assert_eq!(stick.axis(Axis::LeftStickX, -1.0),
+            (Some(KeyCode::Right), Some(KeyCode::Left)));
+        assert_eq!(stick.axis(Axis::LeftStickX, 0.0), (Some(KeyCode::Left), None));
+
+        // up is positive on the stick
+        assert_eq!(stick.axis(Axis::LeftStickY, 1.0), (None, Some(KeyCode::Up)));
+    }
+
+    #[test]
+    fn a_key_tapped_between_ticks_is_pressed_and_released_once()
+    {
+        let mut input = Input::default();
+
+        input.key_down(KeyCode::P);
+        input.key_up(KeyCode::P);
+        tick(&mut input);
+        assert!(input.pressed(Action::Pause));
+        assert!(!input.released(Action::Pause));
+
+        tick(&mut input);
+        assert!(!input.held(Action::Pause));
+        assert!(input.released(Action::Pause));
+
+        tick(&mut input);
+        assert!(!input.released(Action::Pause));
+    }
+
+    #[test]
+    fn a_held_key_is_only_pressed_on_the_first_tick()
+    {
+        let mut input = Input::default();
+
+        input.key_down(KeyCode::Space);
+        tick(&mut input);
+        assert!(input.pressed(Action::Launch));
+
+        for _ in 0..5
+        {
+            // key repeats while it's held aren't new presses
+            input.key_down(KeyCode::Space);
+            tick(&mut input);
+            assert!(input.held(Action::Launch));
+            assert!(!input.pressed(Action::Launch));
+            assert!(!input.released(Action::Launch));
+        }
+
+        input.key_up(KeyCode::Space);
+        tick(&mut input);
+        assert!(input.released(Action::Launch));
+        assert!(!input.held(Action::Launch));
+
+        tick(&mut input);
+        assert!(!input.released(Action::Launch));
+    }
+}
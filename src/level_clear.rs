@@ -2,16 +2,24 @@ use ggez::
 {
     Context,
     GameResult,
-    input::mouse::MouseButton,
+    input::
+    {
+        keyboard::KeyCode,
+        mouse::MouseButton,
+    },
 };
 
-use crate::{BOARD_WIDTH, MAX_LEVEL};
+use crate::BOARD_WIDTH;
 use crate::
 {
     state::State,
     menu::Menu,
-    game::Game,
-    ui::{TextRect, Button},
+    game::{Game, Mode, Score},
+    high_scores::HighScores,
+    name_entry::NameEntry,
+    scores::Table,
+    shared::Shared,
+    ui::{TextRect, Button, focus::{self, FocusKey}},
 };
 
 const BORDER: f32 = 25.0;
@@ -21,30 +29,84 @@ const SIZE: f32 = 35.0;
 pub struct LevelClear
 {
     title: TextRect,
+    name: TextRect,
     info: TextRect,
+    score: TextRect,
+    // asks for a name, if the score made the high score table
+    name_entry: Option<NameEntry>,
 
     next: Option<Button>,
+    high_scores: Button,
     menu: Button,
 
+    time: u32,
     level: u32,
+    points: Score,
+    // the high score table the run goes in
+    table: Table,
 }
 
 impl LevelClear
 {
-    pub fn new(ctx: &mut Context, time: u32, level: u32)
+    // rank is where the score placed in table, if it made it
+    pub fn new(
+        ctx: &mut Context,
+        shared: &Shared,
+        time: u32,
+        level: u32,
+        points: Score,
+        table: Table,
+        rank: Option<usize>)
         -> GameResult<LevelClear>
     {
         let title = TextRect::new(ctx, 0.0, BORDER, "You Win!", None, SIZE, None)
             .center_x(BOARD_WIDTH);
 
-        let info = TextRect::new(ctx, 0.0, title.rect().bottom() + BORDER,
-            format!("time: {}", time),
+        let (name, par, best) = match shared.levels.get(level)
+        {
+            Some(l) => (format!("{} by {}", l.name, l.author), l.par,
+                shared.save.record(&l.id).best_time),
+            None => (format!("Level {}", level), None, None),
+        };
+
+        let name = TextRect::new(ctx, 0.0, title.rect().bottom() + BORDER,
+            name,
+            None, SIZE * 0.5, None)
+            .center_x(BOARD_WIDTH);
+
+        let info = TextRect::new(ctx, 0.0, name.rect().bottom() + BORDER,
+            format!("time: {}{}{}",
+                time,
+                best.map_or(String::new(), |b| format!(" | best: {}", b)),
+                par.map_or(String::new(), |p| format!(" | par: {}", p))),
             None, SIZE * 0.75, None)
             .center_x(BOARD_WIDTH);
 
-        let next = if level < MAX_LEVEL
+        // where the points came from
+        let score = TextRect::new(ctx, 0.0, info.rect().bottom() + BORDER,
+            format!("bricks: {} | best combo: {}", points.bricks, points.best_combo),
+            None, SIZE * 0.5, None)
+            .add_line(ctx, format!("time bonus: {} | lives bonus: {}",
+                points.time_bonus, points.lives_bonus), SIZE * 0.5)
+            .add_line(ctx, format!("score: {}", points.total()), SIZE * 0.75)
+            .center_x(BOARD_WIDTH);
+
+        let mut bottom = score.rect().bottom();
+
+        let name_entry = match rank
+        {
+            Some(rank) =>
+            {
+                let entry = NameEntry::new(ctx, shared, bottom + BORDER / 2.0, table.clone(), rank)?;
+                bottom = entry.bottom();
+                Some(entry)
+            },
+            None => None,
+        };
+
+        let next = if level < shared.levels.count() && shared.unlocked(level + 1)
         {
-            let next_text = TextRect::new(ctx, 0.0, info.rect().bottom() + BORDER,
+            let next_text = TextRect::new(ctx, 0.0, bottom + BORDER,
                 format!("Next Level (Level {})", level + 1),
                 None, SIZE, None)
                 .center_x(BOARD_WIDTH);
@@ -56,55 +118,128 @@ impl LevelClear
             None
         };
 
-        let menu_text = TextRect::new(ctx, 0.0,
-            if let Some(ref next) = &next
-            {
-                next.rect().bottom() + BORDER
-            }
-            else
-            {
-                info.rect().bottom() + BORDER
-            },
-            "Main Menu",
-            None, SIZE, None)
-            .center_x(BOARD_WIDTH);
+        if let Some(ref next) = &next
+        {
+            bottom = next.rect().bottom();
+        }
 
-        let menu = Button::new(ctx, menu_text, BUTTON_BORDER)?;
+        // the high scores and main menu buttons are side by side
+        let high_scores_text = TextRect::new(ctx, 0.0, bottom + BORDER,
+            "High Scores", None, SIZE, None);
+        let mut high_scores = Button::new(ctx, high_scores_text, BUTTON_BORDER)?;
+
+        let menu_text = TextRect::new(ctx, 0.0, bottom + BORDER,
+            "Main Menu", None, SIZE, None);
+        let mut menu = Button::new(ctx, menu_text, BUTTON_BORDER)?;
+
+        let x = BOARD_WIDTH / 2.0 - (high_scores.rect().w + BORDER + menu.rect().w) / 2.0;
+        high_scores.move_to(x, bottom + BORDER);
+        menu.move_to(x + high_scores.rect().w + BORDER, bottom + BORDER);
 
         Ok(LevelClear
         {
             title: title,
+            name: name,
             info: info,
+            score: score,
+            name_entry: name_entry,
 
             next: next,
+            high_scores: high_scores,
             menu: menu,
 
+            time: time,
             level: level,
+            points: points,
+            table: table,
         })
     }
 
-    pub fn update(&mut self, ctx: &mut Context) -> GameResult<Option<State>>
+    fn save_name(&mut self, shared: &mut Shared)
+    {
+        if let Some(ref entry) = self.name_entry
+        {
+            entry.save(shared);
+        }
+    }
+
+    pub fn update(&mut self, ctx: &mut Context, shared: &mut Shared) -> GameResult<Option<State>>
     {
         if let Some(ref mut next) = &mut self.next
         {
             if next.click()
             {
-                return Ok(Some(State::Game(Game::new(ctx, self.level + 1)?)))
+                self.save_name(shared);
+                return Ok(Some(State::Game(Game::new(ctx, shared, Mode::Level(self.level + 1))?)))
             }
         }
+        if self.high_scores.click()
+        {
+            self.save_name(shared);
+            // coming back from the table shows this screen again, without the name box
+            let back = LevelClear::new(ctx, shared, self.time, self.level, self.points,
+                self.table.clone(), None)?;
+            let rank = self.name_entry.as_ref().map(|e| e.rank());
+            return Ok(Some(State::HighScores(HighScores::new(ctx, shared,
+                Some(self.table.clone()), rank, Some(Box::new(State::LevelClear(back))))?)))
+        }
         if self.menu.click()
         {
+            self.save_name(shared);
             return Ok(Some(State::Menu(Menu::new(ctx)?)))
         }
         Ok(None)
     }
 
+    // the buttons in the order the keyboard goes through them
+    fn buttons(&mut self) -> Vec<&mut Button>
+    {
+        let mut v = Vec::new();
+        if let Some(ref mut next) = self.next
+        {
+            v.push(next);
+        }
+        v.push(&mut self.high_scores);
+        v.push(&mut self.menu);
+        v
+    }
+
+    // the name box gets the keys while it's being typed in. otherwise escape goes to
+    // the main menu
+    pub fn key_down(&mut self, ctx: &mut Context, shared: &mut Shared, key: KeyCode, repeat: bool)
+    {
+        let typing = match self.name_entry
+        {
+            Some(ref mut entry) => entry.key_down(ctx, shared, key, repeat),
+            None => false,
+        };
+
+        if !typing && focus::key_down(&mut self.buttons(), key, repeat) == FocusKey::Back
+        {
+            self.menu.press();
+        }
+    }
+
+    pub fn key_up(&mut self, key: KeyCode)
+    {
+        focus::key_up(&mut self.buttons(), key);
+    }
+
+    pub fn text_input(&mut self, ctx: &mut Context, ch: char)
+    {
+        if let Some(ref mut entry) = self.name_entry
+        {
+            entry.text_input(ctx, ch);
+        }
+    }
+
     pub fn mouse_move(&mut self, x: f32, y: f32)
     {
         if let Some(ref mut next) = &mut self.next
         {
             next.mouse_move(x, y);
         }
+        self.high_scores.mouse_move(x, y);
         self.menu.mouse_move(x, y);
     }
 
@@ -114,6 +249,11 @@ impl LevelClear
         {
             next.mouse_down(button, x, y);
         }
+        if let Some(ref mut entry) = self.name_entry
+        {
+            entry.mouse_down(button, x, y);
+        }
+        self.high_scores.mouse_down(button, x, y);
         self.menu.mouse_down(button, x, y);
     }
 
@@ -123,17 +263,25 @@ impl LevelClear
         {
             next.mouse_up(button);
         }
+        self.high_scores.mouse_up(button);
         self.menu.mouse_up(button);
     }
 
     pub fn draw(&mut self, ctx: &mut Context) -> GameResult<()>
     {
         self.title.draw(ctx)?;
+        self.name.draw(ctx)?;
         self.info.draw(ctx)?;
+        self.score.draw(ctx)?;
+        if let Some(ref entry) = self.name_entry
+        {
+            entry.draw(ctx)?;
+        }
         if let Some(ref mut next) = &mut self.next
         {
             next.draw(ctx)?;
         }
+        self.high_scores.draw(ctx)?;
         self.menu.draw(ctx)
     }
 }
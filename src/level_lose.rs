@@ -2,7 +2,11 @@ use ggez::
 {
     Context,
     GameResult,
-    input::mouse::MouseButton,
+    input::
+    {
+        keyboard::KeyCode,
+        mouse::MouseButton,
+    },
 };
 
 use crate::BOARD_WIDTH;
@@ -10,8 +14,11 @@ use crate::
 {
     state::State,
     menu::Menu,
-    game::Game,
-    ui::{TextRect, Button},
+    game::{Game, Mode},
+    name_entry::NameEntry,
+    scores::Table,
+    shared::Shared,
+    ui::{TextRect, Button, focus::{self, FocusKey}},
 };
 
 const BORDER: f32 = 25.0;
@@ -22,16 +29,30 @@ pub struct LevelLose
 {
     title: TextRect,
     info: TextRect,
+    // asks for a name, if the score made the high score table
+    name_entry: Option<NameEntry>,
 
     retry: Button,
     menu: Button,
 
-    level: u32,
+    mode: Mode,
 }
 
 impl LevelLose
 {
-    pub fn new(ctx: &mut Context, time: u32, score: u32, max_score: u32, level: u32)
+    // hits out of max_hits is how much of the level was broken, and rank is where the
+    // score placed in table, if it made it
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        ctx: &mut Context,
+        shared: &Shared,
+        time: u32,
+        score: u32,
+        hits: u32,
+        max_hits: u32,
+        mode: Mode,
+        table: Table,
+        rank: Option<usize>)
         -> GameResult<LevelLose>
     {
         let title = TextRect::new(ctx, 0.0, BORDER,
@@ -39,12 +60,26 @@ impl LevelLose
             .center_x(BOARD_WIDTH);
 
         let info = TextRect::new(ctx, 0.0, title.rect().bottom() + BORDER,
-            format!("time: {} | score: {}/{}", time, score, max_score),
+            format!("time: {} | score: {}", time, score),
             None, SIZE * 0.75, None)
+            .add_line(ctx, format!("bricks: {}/{}", hits, max_hits), SIZE * 0.5)
             .center_x(BOARD_WIDTH);
 
-        let retry_text = TextRect::new(ctx, 0.0, info.rect().bottom() + BORDER,
-            format!("Retry (level {})", level),
+        let mut bottom = info.rect().bottom();
+
+        let name_entry = match rank
+        {
+            Some(rank) =>
+            {
+                let entry = NameEntry::new(ctx, shared, bottom + BORDER / 2.0, table, rank)?;
+                bottom = entry.bottom();
+                Some(entry)
+            },
+            None => None,
+        };
+
+        let retry_text = TextRect::new(ctx, 0.0, bottom + BORDER,
+            format!("Retry ({})", mode),
             None, SIZE, None)
             .center_x(BOARD_WIDTH);
 
@@ -60,26 +95,68 @@ impl LevelLose
         {
             title: title,
             info: info,
+            name_entry: name_entry,
             retry: retry,
             menu: menu,
-            level: level,
+            mode: mode,
         })
     }
 
-    pub fn update(&mut self, ctx: &mut Context) -> GameResult<Option<State>>
+    fn save_name(&mut self, shared: &mut Shared)
+    {
+        if let Some(ref entry) = self.name_entry
+        {
+            entry.save(shared);
+        }
+    }
+
+    // the name is saved on the way out, in case return wasn't pressed
+    pub fn update(&mut self, ctx: &mut Context, shared: &mut Shared) -> GameResult<Option<State>>
     {
         if self.retry.click()
         {
-            return Ok(Some(State::Game(Game::new(ctx, self.level)?)));
+            self.save_name(shared);
+            return Ok(Some(State::Game(Game::new(ctx, shared, self.mode)?)));
         }
         if self.menu.click()
         {
+            self.save_name(shared);
             return Ok(Some(State::Menu(Menu::new(ctx)?)));
         }
 
         Ok(None)
     }
 
+    // the name box gets the keys while it's being typed in. otherwise escape goes to
+    // the main menu
+    pub fn key_down(&mut self, ctx: &mut Context, shared: &mut Shared, key: KeyCode, repeat: bool)
+    {
+        let typing = match self.name_entry
+        {
+            Some(ref mut entry) => entry.key_down(ctx, shared, key, repeat),
+            None => false,
+        };
+
+        if !typing
+            && focus::key_down(&mut [&mut self.retry, &mut self.menu], key, repeat) == FocusKey::Back
+        {
+            self.menu.press();
+        }
+    }
+
+    pub fn key_up(&mut self, key: KeyCode)
+    {
+        focus::key_up(&mut [&mut self.retry, &mut self.menu], key);
+    }
+
+    pub fn text_input(&mut self, ctx: &mut Context, ch: char)
+    {
+        if let Some(ref mut entry) = self.name_entry
+        {
+            entry.text_input(ctx, ch);
+        }
+    }
+
     pub fn mouse_move(&mut self, x: f32, y: f32)
     {
         self.retry.mouse_move(x, y);
@@ -88,6 +165,10 @@ impl LevelLose
 
     pub fn mouse_down(&mut self, button: MouseButton, x: f32, y: f32)
     {
+        if let Some(ref mut entry) = self.name_entry
+        {
+            entry.mouse_down(button, x, y);
+        }
         self.retry.mouse_down(button, x, y);
         self.menu.mouse_down(button, x, y);
     }
@@ -102,6 +183,10 @@ impl LevelLose
     {
         self.title.draw(ctx)?;
         self.info.draw(ctx)?;
+        if let Some(ref entry) = self.name_entry
+        {
+            entry.draw(ctx)?;
+        }
         self.retry.draw(ctx)?;
         self.menu.draw(ctx)
     }
@@ -2,16 +2,23 @@ use ggez::
 {
     Context,
     GameResult,
-    input::mouse::MouseButton,
+    input::
+    {
+        keyboard::KeyCode,
+        mouse::MouseButton,
+    },
 };
 
-use crate::{BOARD_WIDTH, MAX_LEVEL};
+use crate::BOARD_WIDTH;
 use crate::
 {
     state::State,
     menu::Menu,
-    game::Game,
-    ui::{TextRect, Button},
+    game::{Game, Mode},
+    levels::Levels,
+    save::Record,
+    shared::Shared,
+    ui::{TextRect, Button, focus::{self, FocusKey}},
 };
 
 const BORDER: f32 = 25.0;
@@ -20,79 +27,164 @@ const SIZE: f32 = 35.0;
 
 const LEVEL_BORDER: f32 = 10.0;
 const LEVEL_SIZE: f32 = 25.0;
+const LEVEL_SEP: f32 = 15.0;
+const INFO_SIZE: f32 = 14.0;
 
+// the levels are shown in two columns, a page at a time
+const PER_COLUMN: u32 = 5;
+const PER_PAGE: u32 = PER_COLUMN * 2;
 
 pub struct LevelSelect
 {
     title: TextRect,
     levels: Vec<Button>,
     back: Button,
+
+    prev: Button,
+    next: Button,
+
+    // the top of the level buttons
+    top: f32,
+    page: u32,
+    pages: u32,
 }
 
 impl LevelSelect
 {
-    pub fn new(ctx: &mut Context) -> GameResult<LevelSelect>
+    pub fn new(ctx: &mut Context, shared: &Shared) -> GameResult<LevelSelect>
     {
-        let title = TextRect::new(ctx, 0.0, BORDER, "Level Select", None, SIZE, None)
+        let pages = LevelSelect::page_count(&shared.levels);
+
+        let title = TextRect::new(ctx, 0.0, BORDER,
+            LevelSelect::title_text(0, pages), None, SIZE, None)
             .center_x(BOARD_WIDTH);
 
-        let (levels, r) = LevelSelect::make_levels(ctx, title.rect().bottom())?;
+        let top = title.rect().bottom();
+
+        // the buttons below the levels are always in the same place, as if the page were full
+        let level_h = LevelSelect::level_text(ctx, 1, None).rect().h + LEVEL_BORDER * 2.0;
+        let bottom = top + PER_COLUMN as f32 * level_h + (PER_COLUMN - 1) as f32 * LEVEL_SEP;
 
         let back_text = TextRect::new(ctx,
-            0.0, r.bottom() + BORDER,
+            0.0, bottom + BORDER,
             "Back", None, SIZE, None)
             .center_x(BOARD_WIDTH);
 
-
         let back = Button::new(ctx, back_text, BUTTON_BORDER)?;
 
+        let prev_text = TextRect::new(ctx,
+            BORDER + BUTTON_BORDER, bottom + BORDER,
+            "<", None, SIZE, None);
+        let prev = Button::new(ctx, prev_text, BUTTON_BORDER)?;
+
+        let mut next_text = TextRect::new(ctx,
+            0.0, bottom + BORDER,
+            ">", None, SIZE, None);
+        next_text.move_to(
+            BOARD_WIDTH - BORDER - BUTTON_BORDER - next_text.rect().w,
+            next_text.rect().y);
+        let next = Button::new(ctx, next_text, BUTTON_BORDER)?;
+
         Ok(LevelSelect
         {
             title: title,
-            levels: levels,
+            levels: LevelSelect::make_levels(ctx, shared, 0, top)?,
             back: back,
+
+            prev: prev,
+            next: next,
+
+            top: top,
+            page: 0,
+            pages: pages,
         })
     }
 
-    fn make_levels(ctx: &mut Context, y: f32) -> GameResult<(Vec<Button>, ggez::graphics::Rect)>
+    fn title_text(page: u32, pages: u32) -> String
+    {
+        if pages > 1
+        {
+            format!("Level Select ({}/{})", page + 1, pages)
+        }
+        else
+        {
+            "Level Select".to_string()
+        }
+    }
+
+    // there's always a page, even without any levels
+    fn page_count(levels: &Levels) -> u32
+    {
+        u32::max(1, levels.count().div_ceil(PER_PAGE))
+    }
+
+    // the numbers of the levels on a page
+    fn page_range(levels: &Levels, page: u32) -> std::ops::RangeInclusive<u32>
     {
-        let mut r = ggez::graphics::Rect::new(0.0, 0.0, 0.0, 0.0);
+        let first = page * PER_PAGE + 1;
+        let last = u32::min(first + PER_PAGE - 1, levels.count());
 
+        first..=last
+    }
+
+    // the level name, with the saved results under it
+    fn level_text(ctx: &mut Context, level: u32, record: Option<Record>) -> TextRect
+    {
+        let info = match record
+        {
+            Some(Record { cleared: true, best_time, best_score, fewest_lost }) =>
+                format!("{}s | {} pts | {} lost",
+                    best_time.unwrap_or(0),
+                    best_score.unwrap_or(0),
+                    fewest_lost.unwrap_or(0)),
+            Some(Record { best_score: Some(score), .. }) =>
+                format!("best score {}", score),
+            _ => "not cleared".to_string(),
+        };
+
+        TextRect::new(ctx, 0.0, 0.0, format!("Level {}", level), None, LEVEL_SIZE, None)
+            .add_line(ctx, info, INFO_SIZE)
+    }
+
+    fn make_levels(ctx: &mut Context, shared: &Shared, page: u32, y: f32)
+        -> GameResult<Vec<Button>>
+    {
         let mut v = Vec::new();
 
-        let mut left_w = ::std::f32::MIN;
-        let mut right_w = ::std::f32::MIN;
+        let mut left_w = f32::MIN;
+        let mut right_w = f32::MIN;
 
         let mut left = Vec::new();
         let mut right = Vec::new();
 
-        for i in 1..=(MAX_LEVEL / 2)
+        for i in LevelSelect::page_range(&shared.levels, page)
         {
-            let t = TextRect::new(ctx, 0.0, 0.0, format!("Level {}", i), None, LEVEL_SIZE, None);
-            let b = Button::new(ctx, t, LEVEL_BORDER)?;
+            let record = shared.levels.get(i).map(|l| shared.save.record(&l.id));
+            let t = LevelSelect::level_text(ctx, i, record);
+            let mut b = Button::new(ctx, t, LEVEL_BORDER)?;
+            b.set_locked(!shared.unlocked(i));
 
-            if b.rect().w > left_w
+            // the first column fills up before the second
+            if left.len() < PER_COLUMN as usize
             {
-                left_w = b.rect().w;
+                left_w = f32::max(left_w, b.rect().w);
+                left.push(b);
             }
-
-            left.push(b);
-        }
-
-        for i in (MAX_LEVEL / 2 + 1)..=MAX_LEVEL
-        {
-            let t = TextRect::new(ctx, 0.0, 0.0, format!("Level {}", i), None, LEVEL_SIZE, None);
-            let b = Button::new(ctx, t, LEVEL_BORDER)?;
-
-            if b.rect().w > right_w
+            else
             {
-                right_w = b.rect().w;
+                right_w = f32::max(right_w, b.rect().w);
+                right.push(b);
             }
-
-            right.push(b);
         }
 
-        let total_w = left_w + BORDER + right_w;
+        let total_w = if right.is_empty()
+        {
+            left_w
+        }
+        else
+        {
+            left_w + BORDER + right_w
+        };
 
         let offset = (BOARD_WIDTH - total_w) / 2.0;
 
@@ -103,7 +195,7 @@ impl LevelSelect
             let inner_offset = (left_w - b.rect().w) / 2.0;
 
             b.move_to(offset + inner_offset,
-                y + total_h + i as f32 * BORDER);
+                y + total_h + i as f32 * LEVEL_SEP);
             total_h += b.rect().h;
 
             v.push(b);
@@ -116,27 +208,55 @@ impl LevelSelect
             let inner_offset = (right_w - b.rect().w) / 2.0;
 
             b.move_to(offset + left_w + BORDER + inner_offset,
-                y + total_h + i as f32 * BORDER);
+                y + total_h + i as f32 * LEVEL_SEP);
             total_h += b.rect().h;
 
             v.push(b);
         }
 
-        for b in v.iter()
-        {
-            r = r.combine_with(b.rect());
-        }
+        Ok(v)
+    }
 
-        Ok((v, r))
+    fn set_page(&mut self, ctx: &mut Context, shared: &Shared, page: u32) -> GameResult<()>
+    {
+        self.page = page;
+        self.levels = LevelSelect::make_levels(ctx, shared, page, self.top)?;
+        // keep the title centered
+        self.title.change_text(ctx, LevelSelect::title_text(self.page, self.pages));
+        let title = self.title.rect();
+        self.title.move_to(BOARD_WIDTH / 2.0 - title.w / 2.0, title.y);
+
+        Ok(())
     }
 
-    pub fn update(&mut self, ctx: &mut Context) -> GameResult<Option<State>>
+    pub fn update(&mut self, ctx: &mut Context, shared: &mut Shared) -> GameResult<Option<State>>
     {
-        for (i, ref b) in self.levels.iter().enumerate()
+        let first = *LevelSelect::page_range(&shared.levels, self.page).start();
+
+        for (i, b) in self.levels.iter().enumerate()
         {
             if b.click()
             {
-                return Ok(Some(State::Game(Game::new(ctx, (i + 1) as u32)?)))
+                return Ok(Some(State::Game(Game::new(ctx, shared, Mode::Level(first + i as u32))?)))
+            }
+        }
+
+        // the page buttons are reset after use, so holding them down only changes
+        // the page once
+        if self.prev.click()
+        {
+            self.prev.reset();
+            if self.page > 0
+            {
+                self.set_page(ctx, shared, self.page - 1)?;
+            }
+        }
+        if self.next.click()
+        {
+            self.next.reset();
+            if self.page + 1 < self.pages
+            {
+                self.set_page(ctx, shared, self.page + 1)?;
             }
         }
 
@@ -148,6 +268,34 @@ impl LevelSelect
         Ok(None)
     }
 
+    // the buttons in the order the keyboard goes through them, the page buttons
+    // only while they're shown
+    fn buttons(&mut self) -> Vec<&mut Button>
+    {
+        let mut v = self.levels.iter_mut().collect::<Vec<_>>();
+        if self.pages > 1
+        {
+            v.push(&mut self.prev);
+            v.push(&mut self.next);
+        }
+        v.push(&mut self.back);
+        v
+    }
+
+    // escape goes back to the main menu
+    pub fn key_down(&mut self, key: KeyCode, repeat: bool)
+    {
+        if focus::key_down(&mut self.buttons(), key, repeat) == FocusKey::Back
+        {
+            self.back.press();
+        }
+    }
+
+    pub fn key_up(&mut self, key: KeyCode)
+    {
+        focus::key_up(&mut self.buttons(), key);
+    }
+
     pub fn mouse_move(&mut self, x: f32, y: f32)
     {
         for b in self.levels.iter_mut()
@@ -155,6 +303,8 @@ impl LevelSelect
             b.mouse_move(x, y);
         }
 
+        self.prev.mouse_move(x, y);
+        self.next.mouse_move(x, y);
         self.back.mouse_move(x, y);
     }
 
@@ -165,6 +315,11 @@ impl LevelSelect
             b.mouse_down(button, x, y);
         }
 
+        if self.pages > 1
+        {
+            self.prev.mouse_down(button, x, y);
+            self.next.mouse_down(button, x, y);
+        }
         self.back.mouse_down(button, x, y);
     }
 
@@ -175,6 +330,8 @@ impl LevelSelect
             b.mouse_up(button);
         }
 
+        self.prev.mouse_up(button);
+        self.next.mouse_up(button);
         self.back.mouse_up(button);
     }
 
@@ -185,6 +342,44 @@ impl LevelSelect
         {
             b.draw(ctx)?;
         }
+        // the page buttons are only there if there's more than one page
+        if self.pages > 1
+        {
+            self.prev.draw(ctx)?;
+            self.next.draw(ctx)?;
+        }
         self.back.draw(ctx)
     }
 }
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::test_utils;
+
+    #[test]
+    fn the_last_page_has_the_levels_left_over()
+    {
+        let levels = test_utils::levels("level-select-pages", PER_PAGE + 3);
+        assert_eq!(LevelSelect::page_count(&levels), 2);
+        assert_eq!(LevelSelect::page_range(&levels, 0), 1..=PER_PAGE);
+        assert_eq!(LevelSelect::page_range(&levels, 1), PER_PAGE + 1..=PER_PAGE + 3);
+    }
+
+    #[test]
+    fn a_full_page_doesnt_make_another()
+    {
+        let levels = test_utils::levels("level-select-full", PER_PAGE);
+        assert_eq!(LevelSelect::page_count(&levels), 1);
+        assert_eq!(LevelSelect::page_range(&levels, 0), 1..=PER_PAGE);
+    }
+
+    #[test]
+    fn no_levels_is_one_empty_page()
+    {
+        let levels = test_utils::levels("level-select-empty", 0);
+        assert_eq!(LevelSelect::page_count(&levels), 1);
+        assert!(LevelSelect::page_range(&levels, 0).is_empty());
+    }
+}
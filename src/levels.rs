@@ -0,0 +1,539 @@
+use ggez::{GameResult, GameError};
+
+use crate::checksum::Checksum;
+use crate::game::{Effect, Drops, BrickKind, Motion, Creep, MAX_COLUMNS, MAX_ROWS};
+
+use std::path::{Path, PathBuf};
+
+// the directory the level files are loaded from
+const LEVEL_DIR: &str = "levels";
+// the extension of level files, anything else in the directory is ignored
+const LEVEL_EXT: &str = "lvl";
+// the seconds a level without a time limit or a par gets in time attack mode
+const DEFAULT_TIME_LIMIT: u32 = 120;
+
+// a level file looks like this:
+//
+//     # comments start with a '#'
+//     name: Warm Up
+//     author: Someone
+//     par: 30
+//     balls: 2
+//     drop_chance: 0.2
+//     drops: wide slow life
+//     sway: 20 2
+//     orbit: 10 3
+//     creep: 20 seconds
+//     time_limit: 90
+//     ---
+//     .
+//     1 1 1 1 1 1 1 1 | slide 40 4
+//     1 2x 0 s s 0 2r 1@m
+//     2i 1~ 1 1 1 1 1* 2i
+//
+// the header has a name, an author, and optionally a par time in seconds, the
+// number of balls launched at once (1 if it's not there), the chance a broken brick
+// drops a capsule and which capsules they can drop ('none' for nothing). after the
+// '---' each line is a row of bricks, with each number being the hits it takes to
+// break that brick (0 is no brick). a number can have a letter after it for the kind
+// of brick: 'x' explodes, 'r' regenerates and 'i' is invisible, and 's' on its own is
+// a steel brick that can't be broken. '@' and a capsule letter after a brick means
+// it always drops that capsule. a row that is just '.' is an empty row. a row can
+// have at most MAX_COLUMNS bricks, and a level at most MAX_ROWS rows.
+//
+// bricks can move, a '~' after a brick (before any '@') makes it sway side to side
+// and a '*' makes it go round in a circle. the header's 'sway' and 'orbit' are how
+// far they go in pixels and how long they take in seconds. '| slide' after a row
+// makes the whole row sway together, by its own distance and time.
+//
+// 'creep' moves every brick down a row every so many 'seconds' or 'hits' of the
+// paddle, and the level is lost if they reach the paddle
+//
+// 'time_limit' is how many seconds the level gets in time attack mode, if it isn't
+// there it's twice the par time, or DEFAULT_TIME_LIMIT without a par
+pub struct Level
+{
+    // the file name without the extension, used to save progress
+    pub id: String,
+    pub name: String,
+    pub author: String,
+    pub par: Option<u32>,
+    pub balls: u32,
+    pub drops: Drops,
+    pub creep: Option<Creep>,
+    pub time_limit: Option<u32>,
+    pub pattern: Vec<Vec<Cell>>,
+}
+
+// one brick in a level's pattern
+#[derive(Copy, Clone)]
+pub struct Cell
+{
+    // the hits it takes to break, 0 is no brick
+    pub count: u32,
+    // what it always drops
+    pub drop: Option<Effect>,
+    pub kind: BrickKind,
+    pub motion: Motion,
+}
+
+pub struct Levels
+{
+    levels: Vec<Level>,
+}
+
+impl Levels
+{
+    // loads every level file in the level directory, ordered by file name
+    pub fn load() -> GameResult<Levels>
+    {
+        Levels::load_dir(&Levels::dir())
+    }
+
+    pub fn load_dir(dir: &Path) -> GameResult<Levels>
+    {
+        let mut paths = std::fs::read_dir(dir)
+            .map_err(|e| GameError::FilesystemError(
+                format!("could not read level directory {}: {}", dir.display(), e)))?
+            .filter_map(|e| e.ok().map(|e| e.path()))
+            .filter(|p| p.extension().is_some_and(|e| e == LEVEL_EXT))
+            .collect::<Vec<_>>();
+        paths.sort();
+
+        let mut levels = Vec::new();
+        for path in paths
+        {
+            let src = std::fs::read_to_string(&path)
+                .map_err(|e| GameError::FilesystemError(
+                    format!("could not read {}: {}", path.display(), e)))?;
+
+            let mut level = Level::parse(&path.display().to_string(), &src)?;
+            level.id = path.file_stem()
+                .map(|s| s.to_string_lossy().into_owned())
+                .unwrap_or_default();
+            if !valid_id(&level.id)
+            {
+                return Err(GameError::ResourceLoadError(format!(
+                    "{}: level file names can only have letters, numbers, '-' and '_'",
+                    path.display())))
+            }
+
+            levels.push(level);
+        }
+
+        Ok(Levels
+        {
+            levels: levels,
+        })
+    }
+
+    // the level directory, from the crate root when run through cargo,
+    // or the working directory otherwise
+    fn dir() -> PathBuf
+    {
+        match std::env::var_os("CARGO_MANIFEST_DIR")
+        {
+            Some(root) => PathBuf::from(root).join(LEVEL_DIR),
+            None => PathBuf::from(LEVEL_DIR),
+        }
+    }
+
+    // the number of levels, which is also the highest level
+    pub fn count(&self) -> u32
+    {
+        self.levels.len() as u32
+    }
+
+    // the number of the level with the id
+    pub fn find(&self, id: &str) -> Option<u32>
+    {
+        self.levels.iter()
+            .position(|l| l.id == id)
+            .map(|i| i as u32 + 1)
+    }
+
+    // levels are numbered from 1
+    pub fn get(&self, level: u32) -> Option<&Level>
+    {
+        if level == 0
+        {
+            None
+        }
+        else
+        {
+            self.levels.get(level as usize - 1)
+        }
+    }
+}
+
+// the id goes in the save and high score files between spaces, so it's kept to
+// letters, numbers, '-' and '_'
+pub fn valid_id(id: &str) -> bool
+{
+    !id.is_empty() && id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+impl Level
+{
+    // a hash of everything that changes how the level plays, so replays can tell if the level has changed
+    pub fn checksum(&self) -> u64
+    {
+        let mut c = Checksum::new();
+        c.u32(self.balls);
+        c.f32(self.drops.chance);
+        c.u32(self.drops.effects.len() as u32);
+        c.bytes(self.creep.map(|c| c.to_string()).unwrap_or_default().as_bytes());
+        c.u32(self.time_limit.unwrap_or(0));
+        // the par gives the clear bonus without a time limit
+        c.u32(self.par.unwrap_or(0));
+        for e in &self.drops.effects
+        {
+            c.u32(e.code());
+        }
+        for row in &self.pattern
+        {
+            c.u32(row.len() as u32);
+            for b in row
+            {
+                c.u32(b.count);
+                c.u32(b.kind.code());
+                b.motion.hash(&mut c);
+                c.u32(b.drop.map(|e| e.code()).unwrap_or(0));
+            }
+        }
+        c.finish()
+    }
+
+    // file is only used for error messages
+    pub fn parse(file: &str, src: &str) -> GameResult<Level>
+    {
+        let error = |line: usize, msg: String|
+        {
+            GameError::ResourceLoadError(format!("{}:{}: {}", file, line + 1, msg))
+        };
+
+        let mut name = None;
+        let mut author = None;
+        let mut par = None;
+        let mut balls = 1;
+        let mut drops = Drops::default();
+        let mut sway = Motion::Sway { amplitude: 20.0, period: 2.0 };
+        let mut orbit = Motion::Orbit { radius: 10.0, period: 3.0 };
+        let mut creep = None;
+        let mut time_limit = None;
+        let mut pattern = Vec::new();
+
+        let mut in_header = true;
+        let mut last_line = 0;
+
+        for (i, line) in src.lines().enumerate()
+        {
+            last_line = i;
+            let line = line.trim();
+
+            // skip blank lines and comments
+            if line.is_empty() || line.starts_with('#')
+            {
+                continue;
+            }
+
+            if in_header
+            {
+                if line == "---"
+                {
+                    in_header = false;
+                    continue;
+                }
+
+                let (key, value) = match line.find(':')
+                {
+                    Some(c) => (line[..c].trim(), line[c + 1..].trim()),
+                    None => return Err(error(i, format!("expected 'key: value', found '{}'", line))),
+                };
+
+                match key
+                {
+                    "name" => name = Some(value.to_string()),
+                    "author" => author = Some(value.to_string()),
+                    "par" => par = Some(value.parse::<u32>()
+                        .map_err(|_| error(i, format!("invalid par time '{}'", value)))?),
+                    "balls" => balls = match value.parse::<u32>()
+                    {
+                        Ok(n) if n > 0 => n,
+                        _ => return Err(error(i, format!("invalid number of balls '{}'", value))),
+                    },
+                    "drop_chance" => drops.chance = match value.parse::<f32>()
+                    {
+                        Ok(c) if (0.0..=1.0).contains(&c) => c,
+                        _ => return Err(error(i, format!("invalid drop chance '{}'", value))),
+                    },
+                    "drops" if value == "none" => drops.effects = Vec::new(),
+                    "drops" => drops.effects = value.split_whitespace()
+                        .map(|e| Effect::from_name(e)
+                            .ok_or_else(|| error(i, format!("unknown capsule '{}'", e))))
+                        .collect::<GameResult<Vec<_>>>()?,
+                    "creep" => creep = match Creep::parse(value)
+                    {
+                        Some(c) => Some(c),
+                        None => return Err(error(i, format!(
+                            "invalid creep '{}', expected like '20 seconds' or '6 hits'", value))),
+                    },
+                    "time_limit" => time_limit = match value.parse::<u32>()
+                    {
+                        Ok(t) if t > 0 => Some(t),
+                        _ => return Err(error(i, format!("invalid time limit '{}'", value))),
+                    },
+                    "sway" => sway = match Level::parse_motion(value)
+                    {
+                        Some((amplitude, period)) => Motion::Sway
+                        {
+                            amplitude: amplitude,
+                            period: period,
+                        },
+                        None => return Err(error(i, format!("invalid sway '{}'", value))),
+                    },
+                    "orbit" => orbit = match Level::parse_motion(value)
+                    {
+                        Some((radius, period)) => Motion::Orbit
+                        {
+                            radius: radius,
+                            period: period,
+                        },
+                        None => return Err(error(i, format!("invalid orbit '{}'", value))),
+                    },
+                    _ => return Err(error(i, format!("unknown header key '{}'", key))),
+                }
+            }
+            else if pattern.len() >= MAX_ROWS
+            {
+                return Err(error(i, format!("too many rows, a level can have at most {}", MAX_ROWS)))
+            }
+            else if line == "."
+            {
+                pattern.push(Vec::new());
+            }
+            else
+            {
+                let (line, slide) = match line.find('|')
+                {
+                    Some(bar) =>
+                    {
+                        let slide = match line[bar + 1..].trim().strip_prefix("slide")
+                            .and_then(Level::parse_motion)
+                        {
+                            Some((amplitude, period)) => Motion::Sway
+                            {
+                                amplitude: amplitude,
+                                period: period,
+                            },
+                            None => return Err(error(i,
+                                format!("invalid row motion '{}'", line[bar + 1..].trim()))),
+                        };
+                        (&line[..bar], Some(slide))
+                    },
+                    None => (line, None),
+                };
+
+                let mut row = line.split_whitespace()
+                    .map(|c| Level::parse_cell(c, sway, orbit)
+                        .ok_or_else(|| error(i, format!("invalid brick '{}'", c))))
+                    .collect::<GameResult<Vec<_>>>()?;
+                if row.len() > MAX_COLUMNS
+                {
+                    return Err(error(i, format!("row has {} bricks, but only {} fit across the board",
+                        row.len(), MAX_COLUMNS)))
+                }
+
+                if let Some(slide) = slide
+                {
+                    for cell in row.iter_mut()
+                    {
+                        cell.motion = slide;
+                    }
+                }
+
+                pattern.push(row);
+            }
+        }
+
+        if in_header
+        {
+            return Err(error(last_line, "missing '---' after the header".to_string()))
+        }
+        // steel bricks can't be broken, so a level of only steel could never be cleared
+        if !pattern.iter().flatten().any(|c| c.count > 0 && c.kind.must_clear())
+        {
+            return Err(error(last_line, "the level has no bricks to break".to_string()))
+        }
+
+        Ok(Level
+        {
+            id: String::new(),
+            name: name.ok_or_else(|| error(0, "missing 'name' in header".to_string()))?,
+            author: author.ok_or_else(|| error(0, "missing 'author' in header".to_string()))?,
+            par: par,
+            balls: balls,
+            drops: drops,
+            creep: creep,
+            time_limit: time_limit,
+            pattern: pattern,
+        })
+    }
+
+    // the seconds the level gets in time attack mode
+    pub fn attack_time(&self) -> u32
+    {
+        self.time_limit
+            .or(self.par.map(|p| p * 2))
+            .unwrap_or(DEFAULT_TIME_LIMIT)
+    }
+
+    // how far and how long, for moving bricks
+    fn parse_motion(s: &str) -> Option<(f32, f32)>
+    {
+        let v = s.split_whitespace().collect::<Vec<_>>();
+        match v[..]
+        {
+            [distance, period] =>
+            {
+                let distance = distance.parse::<f32>().ok()?;
+                let period = period.parse::<f32>().ok()?;
+                if distance >= 0.0 && period > 0.0
+                {
+                    Some((distance, period))
+                }
+                else
+                {
+                    None
+                }
+            },
+            _ => None,
+        }
+    }
+
+    // a number and maybe a kind letter, or 's', then maybe '~' or '*' for how it moves,
+    // then maybe '@' and a capsule letter
+    fn parse_cell(s: &str, sway: Motion, orbit: Motion) -> Option<Cell>
+    {
+        let (count, drop) = match s.find('@')
+        {
+            Some(at) =>
+            {
+                let mut letters = s[at + 1..].chars();
+                match (letters.next().and_then(Effect::from_letter), letters.next())
+                {
+                    (Some(e), None) => (&s[..at], Some(e)),
+                    _ => return None,
+                }
+            },
+            None => (s, None),
+        };
+
+        let (count, motion) = if let Some(count) = count.strip_suffix('~')
+        {
+            (count, sway)
+        }
+        else if let Some(count) = count.strip_suffix('*')
+        {
+            (count, orbit)
+        }
+        else
+        {
+            (count, Motion::Still)
+        };
+
+        let (count, kind) = match count
+        {
+            // steel bricks still need a count to be a brick at all
+            "s" => (1, BrickKind::Steel),
+            _ => match count.char_indices().last()?
+            {
+                (i, 'x') => (count[..i].parse().ok()?, BrickKind::Explosive),
+                (i, 'r') => (count[..i].parse().ok()?, BrickKind::Regen),
+                (i, 'i') => (count[..i].parse().ok()?, BrickKind::Invisible),
+                _ => (count.parse().ok()?, BrickKind::Normal),
+            },
+        };
+
+        Some(Cell
+        {
+            count: count,
+            drop: drop,
+            kind: kind,
+            motion: motion,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    fn level(rows: &[String]) -> GameResult<Level>
+    {
+        Level::parse("test.lvl", &format!("name: Test\nauthor: Someone\n---\n{}", rows.join("\n")))
+    }
+
+    fn error(result: GameResult<Level>) -> String
+    {
+        match result
+        {
+            Err(GameError::ResourceLoadError(e)) => e,
+            Err(e) => panic!("unexpected error {}", e),
+            Ok(_) => panic!("the level loaded"),
+        }
+    }
+
+    #[test]
+    fn the_shipped_levels_load()
+    {
+        let levels = Levels::load().unwrap();
+        assert_eq!(levels.get(levels.find("10").unwrap()).unwrap().balls, 1);
+        assert_eq!(levels.get(levels.find("14").unwrap()).unwrap().balls, 2);
+    }
+
+    #[test]
+    fn changing_the_par_changes_the_checksum()
+    {
+        let rows = vec!["1 1".to_string()];
+        let mut level = level(&rows).unwrap();
+        let before = level.checksum();
+        level.par = Some(30);
+        assert_ne!(level.checksum(), before);
+    }
+
+    #[test]
+    fn ids_are_kept_to_one_word()
+    {
+        assert!(valid_id("01"));
+        assert!(valid_id("bonus_round-2"));
+        assert!(!valid_id(""));
+        assert!(!valid_id("my level"));
+        assert!(!valid_id("caf\u{e9}"));
+    }
+
+    #[test]
+    fn a_full_row_and_the_most_rows_load()
+    {
+        let mut rows = vec![vec!["1"; MAX_COLUMNS].join(" ")];
+        rows.extend(vec![".".to_string(); MAX_ROWS - 1]);
+        let level = level(&rows).unwrap();
+        assert_eq!(level.pattern.len(), MAX_ROWS);
+        assert_eq!(level.pattern[0].len(), MAX_COLUMNS);
+    }
+
+    #[test]
+    fn a_row_too_wide_is_an_error_on_its_line()
+    {
+        let rows = vec!["1".to_string(), vec!["1"; MAX_COLUMNS + 1].join(" ")];
+        assert!(error(level(&rows)).starts_with("test.lvl:5: row has 16 bricks"));
+    }
+
+    #[test]
+    fn too_many_rows_is_an_error_on_the_first_extra_one()
+    {
+        let rows = vec!["1".to_string(); MAX_ROWS + 2];
+        assert!(error(level(&rows)).starts_with(&format!("test.lvl:{}: too many rows", 4 + MAX_ROWS)));
+    }
+}
@@ -1,3 +1,6 @@
+// the code is written with explicit struct fields
+#![allow(clippy::redundant_field_names)]
+
 use ggez::
 {
     ContextBuilder,
@@ -20,21 +23,35 @@ const FOREHEAD: f32 = 50f32;
 const WIDTH: f32 = BOARD_WIDTH;
 const HEIGHT: f32 = BOARD_HEIGHT + FOREHEAD;
 
-// the highest level
-const MAX_LEVEL: u32 = 10;
-
 mod ui;
+mod checksum;
+mod rng;
+mod input;
+mod levels;
+mod replay;
+mod save;
+mod scores;
+mod settings;
+mod shared;
 mod window;
 mod state;
 mod menu;
 mod level_select;
 mod game;
+mod name_entry;
 mod level_clear;
 mod level_lose;
+mod replay_select;
+mod endless_select;
+mod daily;
+mod high_scores;
+mod controls;
+#[cfg(test)]
+mod test_utils;
 
 fn main() -> GameResult<()>
 {
-    let (mut ctx, mut eloop) = ContextBuilder::new("...", "...")
+    let (mut ctx, mut eloop) = ContextBuilder::new("brick-breaker", "Matthew Maclean")
         .window_setup(WindowSetup::default()
             .title("Brick Breaker"))
         .window_mode(WindowMode::default()
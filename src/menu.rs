@@ -2,7 +2,11 @@ use ggez::
 {
     Context,
     GameResult,
-    input::mouse::MouseButton,
+    input::
+    {
+        keyboard::KeyCode,
+        mouse::MouseButton,
+    },
 };
 
 use crate::BOARD_WIDTH;
@@ -11,18 +15,47 @@ use crate::
 {
     state::State,
     level_select::LevelSelect,
-    ui::{TextRect, Button},
+    replay_select::ReplaySelect,
+    endless_select::EndlessSelect,
+    daily::Daily,
+    high_scores::HighScores,
+    controls::Controls,
+    shared::Shared,
+    ui::{TextRect, Button, focus},
 };
 
 const BORDER: f32 = 25.0;
-const BUTTON_BORDER: f32 = 15.0;
-const SIZE: f32 = 35.0;
+const BUTTON_BORDER: f32 = 10.0;
+const BUTTON_SEP: f32 = 12.0;
+const SIZE: f32 = 28.0;
+
+// what each of the menu's buttons goes to
+#[derive(Copy, Clone)]
+enum Choice
+{
+    LevelSelect,
+    Endless,
+    Daily,
+    HighScores,
+    Replays,
+    Controls,
+    Exit,
+}
+
+const CHOICES: [(Choice, &str); 7] = [
+    (Choice::LevelSelect, "Level Select"),
+    (Choice::Endless, "Endless"),
+    (Choice::Daily, "Daily Challenge"),
+    (Choice::HighScores, "High Scores"),
+    (Choice::Replays, "Replays"),
+    (Choice::Controls, "Controls"),
+    (Choice::Exit, "Exit"),
+];
 
 pub struct Menu
 {
     title: TextRect,
-    level_select: Button,
-    exit: Button,
+    buttons: Vec<(Button, Choice)>,
 }
 
 impl Menu
@@ -32,74 +65,95 @@ impl Menu
         let title = TextRect::new(ctx, 0.0, BORDER, "Brick Breaker", None, 35.0, None)
             .center_x(BOARD_WIDTH);
 
-        let level_select_text = TextRect::new(
-            ctx,
-            0.0,
-            title.rect().bottom() + BORDER,
-            "Level Select",
-            None,
-            SIZE,
-            None)
-            .center_x(BOARD_WIDTH);
-
-        let level_select = Button::new(ctx, level_select_text, BUTTON_BORDER)?;
-
-        let exit_text = TextRect::new(
-            ctx,
-            0.0,
-            level_select.rect().bottom() + BORDER,
-            "Exit",
-            None,
-            SIZE,
-            None)
-            .center_x(BOARD_WIDTH);
+        // the buttons go down the screen, one under the other
+        let mut buttons = Vec::new();
+        let mut y = title.rect().bottom() + BORDER;
+        for (choice, text) in CHOICES.iter()
+        {
+            let text = TextRect::new(ctx, 0.0, y, text, None, SIZE, None)
+                .center_x(BOARD_WIDTH);
+            let button = Button::new(ctx, text, BUTTON_BORDER)?;
+            y = button.rect().bottom() + BUTTON_SEP;
 
-        let exit = Button::new(ctx, exit_text, BUTTON_BORDER)?;
+            buttons.push((button, *choice));
+        }
 
         Ok(Menu
         {
             title: title,
-            level_select: level_select,
-            exit: exit,
+            buttons: buttons,
         })
     }
 
-    pub fn update(&mut self, ctx: &mut Context) -> GameResult<Option<State>>
+    pub fn update(&mut self, ctx: &mut Context, shared: &mut Shared) -> GameResult<Option<State>>
     {
-        if self.level_select.click()
+        let choice = match self.buttons.iter().find(|(b, _)| b.click())
         {
-            return Ok(Some(State::LevelSelect(LevelSelect::new(ctx)?)))
-        }
-        if self.exit.click()
+            Some((_, choice)) => *choice,
+            None => return Ok(None),
+        };
+
+        Ok(Some(match choice
         {
-            ggez::event::quit(ctx)
-        }
+            Choice::LevelSelect => State::LevelSelect(LevelSelect::new(ctx, shared)?),
+            Choice::Endless => State::EndlessSelect(EndlessSelect::new(ctx, shared)?),
+            Choice::Daily => State::Daily(Daily::new(ctx, shared, None)?),
+            Choice::HighScores => State::HighScores(HighScores::new(ctx, shared, None, None, None)?),
+            Choice::Replays => State::ReplaySelect(ReplaySelect::new(ctx, shared, None)?),
+            Choice::Controls => State::Controls(Controls::new(ctx, shared)?),
+            Choice::Exit =>
+            {
+                ggez::event::quit(ctx);
+                return Ok(None)
+            },
+        }))
+    }
 
-        Ok(None)
+    // the main menu is as far back as it goes, so escape does nothing
+    pub fn key_down(&mut self, key: KeyCode, repeat: bool)
+    {
+        let mut buttons = self.buttons.iter_mut().map(|(b, _)| b).collect::<Vec<_>>();
+        focus::key_down(&mut buttons, key, repeat);
+    }
+
+    pub fn key_up(&mut self, key: KeyCode)
+    {
+        let mut buttons = self.buttons.iter_mut().map(|(b, _)| b).collect::<Vec<_>>();
+        focus::key_up(&mut buttons, key);
     }
 
     pub fn mouse_move(&mut self, x: f32, y: f32)
     {
-        self.level_select.mouse_move(x, y);
-        self.exit.mouse_move(x, y);
+        for (b, _) in self.buttons.iter_mut()
+        {
+            b.mouse_move(x, y);
+        }
     }
 
     pub fn mouse_down(&mut self, button: MouseButton, x: f32, y: f32)
     {
-        self.level_select.mouse_down(button, x, y);
-        self.exit.mouse_down(button, x, y);
+        for (b, _) in self.buttons.iter_mut()
+        {
+            b.mouse_down(button, x, y);
+        }
     }
 
     pub fn mouse_up(&mut self, button: MouseButton)
     {
-        self.level_select.mouse_up(button);
-        self.exit.mouse_up(button);
+        for (b, _) in self.buttons.iter_mut()
+        {
+            b.mouse_up(button);
+        }
     }
 
     pub fn draw(&self, ctx: &mut Context) -> GameResult<()>
     {
         self.title.draw(ctx)?;
-        self.level_select.draw(ctx)?;
-        self.exit.draw(ctx)
+        for (b, _) in &self.buttons
+        {
+            b.draw(ctx)?;
+        }
+
+        Ok(())
     }
 }
@@ -0,0 +1,127 @@
+use ggez::
+{
+    Context,
+    GameResult,
+    input::
+    {
+        keyboard::KeyCode,
+        mouse::MouseButton,
+    },
+};
+
+use crate::BOARD_WIDTH;
+use crate::
+{
+    scores::{self, Table, MAX_NAME},
+    shared::Shared,
+    ui::{TextRect, TextInput},
+};
+
+const SIZE: f32 = 17.5;
+const BORDER: f32 = 6.0;
+const SEP: f32 = 5.0;
+
+// asks for a name for a score that made its high score table. the score is already
+// in the table under the last name used, this changes it
+pub struct NameEntry
+{
+    prompt: TextRect,
+    input: TextInput,
+
+    table: Table,
+    // where the score placed in the table
+    rank: usize,
+}
+
+impl NameEntry
+{
+    // it's centered across the board with its top at y, and starts out focused
+    pub fn new(ctx: &mut Context, shared: &Shared, y: f32, table: Table, rank: usize)
+        -> GameResult<NameEntry>
+    {
+        let prompt = TextRect::new(ctx, 0.0, y,
+            format!("New high score, #{}! Your name:", rank + 1),
+            None, SIZE, None)
+            .center_x(BOARD_WIDTH);
+        let mut input = TextInput::new(ctx, 0.0, prompt.rect().bottom() + SEP,
+            &shared.settings.name, MAX_NAME, SIZE, BORDER)?
+            .center_x(BOARD_WIDTH);
+        input.set_focused(true);
+
+        Ok(NameEntry
+        {
+            prompt: prompt,
+            input: input,
+
+            table: table,
+            rank: rank,
+        })
+    }
+
+    // the bottom of the name box
+    pub fn bottom(&self) -> f32
+    {
+        self.input.rect().bottom()
+    }
+
+    pub fn rank(&self) -> usize
+    {
+        self.rank
+    }
+
+    // puts the typed name on the score, and remembers it for next time
+    pub fn save(&self, shared: &mut Shared)
+    {
+        shared.scores.rename(&self.table, self.rank, self.input.value());
+        shared.scores.write();
+
+        shared.settings.name = scores::clean_name(self.input.value());
+        shared.settings.write();
+    }
+
+    // while the name is being typed return finishes it, escape leaves it as it is,
+    // and the rest of the keys type. gives false for the keys that move the focus
+    // and for every key when it isn't being typed in, so the screen can use them
+    pub fn key_down(&mut self, ctx: &mut Context, shared: &mut Shared, key: KeyCode, repeat: bool)
+        -> bool
+    {
+        if !self.input.focused()
+        {
+            return false
+        }
+
+        match key
+        {
+            KeyCode::Return | KeyCode::NumpadEnter => if !repeat
+            {
+                self.save(shared);
+                self.input.set_focused(false);
+            },
+            KeyCode::Escape => self.input.set_focused(false),
+            KeyCode::Up | KeyCode::Down | KeyCode::Tab =>
+            {
+                self.input.set_focused(false);
+                return false
+            },
+            _ => self.input.key_down(ctx, key),
+        }
+
+        true
+    }
+
+    pub fn text_input(&mut self, ctx: &mut Context, ch: char)
+    {
+        self.input.text_input(ctx, ch);
+    }
+
+    pub fn mouse_down(&mut self, button: MouseButton, x: f32, y: f32)
+    {
+        self.input.mouse_down(button, x, y);
+    }
+
+    pub fn draw(&self, ctx: &mut Context) -> GameResult<()>
+    {
+        self.prompt.draw(ctx)?;
+        self.input.draw(ctx)
+    }
+}
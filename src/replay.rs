@@ -0,0 +1,532 @@
+use ggez::{GameResult, GameError};
+
+use crate::game::{Creep, TICKS_PER_SECOND};
+
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// the directory replays are kept in, in the user's config directory
+const REPLAY_DIR: &str = "replays";
+const REPLAY_EXT: &str = "txt";
+// bump this when the format or the simulation changes, older replays can't be played
+const REPLAY_VERSION: u32 = 1;
+// a checksum of the simulation is kept every this many steps
+const CHECK_EVERY: u32 = 60;
+// how many runs or checks are written on each line
+const PER_LINE: usize = 16;
+// the most steps a replay can have, twelve hours of them, so a broken file can't
+// ask for any amount of memory
+const MAX_STEPS: usize = 12 * 60 * 60 * TICKS_PER_SECOND as usize;
+
+// a recorded run: the input for every step of the simulation, and checksums of the
+// simulation along the way. a replay file looks like this:
+//
+//     version <REPLAY_VERSION>
+//     level 01 9c3f0a1b2c3d4e5f
+//     seed 0123456789abcdef
+//     creep none
+//     time none
+//     result clear 40 12
+//     inputs 35x0 12x1 1x4 700x0
+//     checks 60:1a2b3c4d5e6f7a8b 120:...
+//
+// the version is the REPLAY_VERSION it was written with. the level line has the level
+// id and a checksum of the level, or for endless mode there's an 'endless' line with
+// its seed instead, like 'endless 0a1b2c3d', and for a daily challenge a 'daily' line
+// with its day, like 'daily 20379'. the seed is what the random parts of the game
+// were seeded with, creep is how often the bricks moved down ('none', or like
+// '20 seconds' since creep mode can be turned on for any level), time is the time
+// limit in seconds for time attack ('none' without it), the result is whether it was
+// cleared, the score and the time. inputs are runs of 'count x input', where the
+// input is the keys and the mouse packed into a number, and there can be as many
+// inputs and checks lines as needed
+pub struct Replay
+{
+    pub source: Source,
+    pub seed: u64,
+    pub creep: Option<Creep>,
+    pub time_limit: Option<u32>,
+
+    pub cleared: bool,
+    pub score: u32,
+    pub time: u32,
+
+    // one for each step
+    inputs: Vec<u32>,
+    // (step, checksum after that step)
+    checks: Vec<(u32, u64)>,
+}
+
+// what a replay was recorded on
+pub enum Source
+{
+    // the level's id, and a checksum of it
+    Level(String, u64),
+    // the seed of endless mode
+    Endless(u32),
+    // the day of a daily challenge
+    Daily(u32),
+}
+
+impl Replay
+{
+    pub fn dir(config_dir: &Path) -> PathBuf
+    {
+        config_dir.join(REPLAY_DIR)
+    }
+
+    // all the replay files, newest first
+    pub fn list(dir: &Path) -> Vec<PathBuf>
+    {
+        let mut paths = match std::fs::read_dir(dir)
+        {
+            Ok(entries) => entries
+                .filter_map(|e| e.ok().map(|e| e.path()))
+                .filter(|p| p.extension().is_some_and(|e| e == REPLAY_EXT))
+                .collect::<Vec<_>>(),
+            // no directory just means no replays yet
+            Err(_) => Vec::new(),
+        };
+
+        // the file names start with the time, so this puts the newest first
+        paths.sort_by(|a, b| b.cmp(a));
+        paths
+    }
+
+    pub fn load(path: &Path) -> GameResult<Replay>
+    {
+        let src = std::fs::read_to_string(path)
+            .map_err(|e| GameError::FilesystemError(
+                format!("could not read {}: {}", path.display(), e)))?;
+
+        Replay::parse(&path.display().to_string(), &src)
+    }
+
+    // file is only used for error messages
+    fn parse(file: &str, src: &str) -> GameResult<Replay>
+    {
+        let error = |line: usize, msg: String|
+        {
+            GameError::ResourceLoadError(format!("{}:{}: {}", file, line + 1, msg))
+        };
+        let number = |line: usize, s: &str| -> GameResult<u32>
+        {
+            s.parse().map_err(|_| error(line, format!("invalid number '{}'", s)))
+        };
+        let hash = |line: usize, s: &str| -> GameResult<u64>
+        {
+            u64::from_str_radix(s, 16).map_err(|_| error(line, format!("invalid checksum '{}'", s)))
+        };
+
+        let mut version = None;
+        let mut source = None;
+        let mut seed = None;
+        let mut creep = None;
+        let mut time_limit = None;
+        let mut result = None;
+        let mut inputs = Vec::new();
+        let mut checks = Vec::new();
+
+        for (i, line) in src.lines().enumerate()
+        {
+            let v = line.split_whitespace().collect::<Vec<_>>();
+
+            match v.first()
+            {
+                None => { },
+                Some(&"version") if v.len() == 2 => version = Some(number(i, v[1])?),
+                Some(&"level") if v.len() == 3 =>
+                {
+                    source = Some(Source::Level(v[1].to_string(), hash(i, v[2])?));
+                },
+                Some(&"endless") if v.len() == 2 => match u32::from_str_radix(v[1], 16)
+                {
+                    Ok(seed) => source = Some(Source::Endless(seed)),
+                    Err(_) => return Err(error(i, format!("invalid seed '{}'", v[1]))),
+                },
+                Some(&"daily") if v.len() == 2 => source = Some(Source::Daily(number(i, v[1])?)),
+                Some(&"seed") if v.len() == 2 => seed = Some(hash(i, v[1])?),
+                Some(&"creep") if v[1..] == ["none"] => creep = Some(None),
+                Some(&"creep") => match Creep::parse(&v[1..].join(" "))
+                {
+                    Some(c) => creep = Some(Some(c)),
+                    None => return Err(error(i, format!("invalid creep '{}'", line))),
+                },
+                Some(&"time") if v[1..] == ["none"] => time_limit = Some(None),
+                Some(&"time") if v.len() == 2 => time_limit = Some(Some(number(i, v[1])?)),
+                Some(&"result") if v.len() == 4 =>
+                {
+                    let cleared = match v[1]
+                    {
+                        "clear" => true,
+                        "lose" => false,
+                        r => return Err(error(i, format!("unknown result '{}'", r))),
+                    };
+                    result = Some((cleared, number(i, v[2])?, number(i, v[3])?));
+                },
+                Some(&"inputs") =>
+                {
+                    for run in &v[1..]
+                    {
+                        let (count, bits) = match run.find('x')
+                        {
+                            Some(x) => (number(i, &run[..x])?, number(i, &run[x + 1..])?),
+                            None => return Err(error(i, format!("invalid input run '{}'", run))),
+                        };
+                        if inputs.len() + count as usize > MAX_STEPS
+                        {
+                            return Err(error(i, format!("more than {} steps", MAX_STEPS)))
+                        }
+                        inputs.resize(inputs.len() + count as usize, bits);
+                    }
+                },
+                Some(&"checks") =>
+                {
+                    for check in &v[1..]
+                    {
+                        match check.find(':')
+                        {
+                            Some(c) => checks.push(
+                                (number(i, &check[..c])?, hash(i, &check[c + 1..])?)),
+                            None => return Err(error(i, format!("invalid check '{}'", check))),
+                        }
+                    }
+                },
+                Some(_) => return Err(error(i, format!("unexpected line '{}'", line))),
+            }
+        }
+
+        match version
+        {
+            Some(REPLAY_VERSION) => { },
+            Some(v) => return Err(error(0, format!(
+                "replay is version {}, but only version {} can be played", v, REPLAY_VERSION))),
+            None => return Err(error(0, "missing version".to_string())),
+        }
+
+        let source = source.ok_or_else(|| error(0, "missing level".to_string()))?;
+        let seed = seed.ok_or_else(|| error(0, "missing seed".to_string()))?;
+        let creep = creep.ok_or_else(|| error(0, "missing creep".to_string()))?;
+        let time_limit = time_limit.ok_or_else(|| error(0, "missing time".to_string()))?;
+        let (cleared, score, time) = result.ok_or_else(|| error(0, "missing result".to_string()))?;
+
+        Ok(Replay
+        {
+            source: source,
+            seed: seed,
+            creep: creep,
+            time_limit: time_limit,
+
+            cleared: cleared,
+            score: score,
+            time: time,
+
+            inputs: inputs,
+            checks: checks,
+        })
+    }
+
+    // writes the replay to a new file in dir, failing to save is reported but
+    // doesn't stop the game
+    pub fn write(&self, dir: &Path)
+    {
+        let mut out = format!("version {}\n", REPLAY_VERSION);
+        let name = match self.source
+        {
+            Source::Level(ref id, hash) =>
+            {
+                out += &format!("level {} {:016x}\n", id, hash);
+                id.clone()
+            },
+            Source::Endless(seed) =>
+            {
+                out += &format!("endless {:08x}\n", seed);
+                "endless".to_string()
+            },
+            Source::Daily(day) =>
+            {
+                out += &format!("daily {}\n", day);
+                "daily".to_string()
+            },
+        };
+        out += &format!("seed {:016x}\n", self.seed);
+        match self.creep
+        {
+            Some(creep) => out += &format!("creep {}\n", creep),
+            None => out += "creep none\n",
+        }
+        match self.time_limit
+        {
+            Some(t) => out += &format!("time {}\n", t),
+            None => out += "time none\n",
+        }
+        out += &format!("result {} {} {}\n",
+            if self.cleared { "clear" } else { "lose" }, self.score, self.time);
+
+        // run length encode the inputs, they're mostly long runs of the same thing
+        let mut runs = Vec::<(u32, u32)>::new();
+        for &bits in &self.inputs
+        {
+            match runs.last_mut()
+            {
+                Some((count, last)) if *last == bits => *count += 1,
+                _ => runs.push((1, bits)),
+            }
+        }
+        for line in runs.chunks(PER_LINE)
+        {
+            out += "inputs";
+            for (count, bits) in line
+            {
+                out += &format!(" {}x{}", count, bits);
+            }
+            out += "\n";
+        }
+        for line in self.checks.chunks(PER_LINE)
+        {
+            out += "checks";
+            for (step, hash) in line
+            {
+                out += &format!(" {}:{:016x}", step, hash);
+            }
+            out += "\n";
+        }
+
+        // the time goes first so the files sort by age, and if there's somehow
+        // already a replay from the same millisecond a number goes on the end
+        let millis = SystemTime::now().duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis())
+            .unwrap_or(0);
+        let mut path = dir.join(format!("{}-{}.{}", millis, name, REPLAY_EXT));
+        let mut n = 1;
+        while path.exists()
+        {
+            n += 1;
+            path = dir.join(format!("{}-{}-{}.{}", millis, name, n, REPLAY_EXT));
+        }
+
+        if let Err(e) = std::fs::create_dir_all(dir).and_then(|_| std::fs::write(&path, out))
+        {
+            eprintln!("warning: could not write replay {}: {}", path.display(), e);
+        }
+    }
+}
+
+// builds up a replay as the game is played
+pub struct Recorder
+{
+    seed: u64,
+    creep: Option<Creep>,
+    time_limit: Option<u32>,
+    inputs: Vec<u32>,
+    checks: Vec<(u32, u64)>,
+}
+
+impl Recorder
+{
+    // seed is what the game was seeded with, creep is how often the bricks move down
+    // and time_limit is the seconds the level has in time attack
+    pub fn new(seed: u64, creep: Option<Creep>, time_limit: Option<u32>) -> Recorder
+    {
+        Recorder
+        {
+            seed: seed,
+            creep: creep,
+            time_limit: time_limit,
+            inputs: Vec::new(),
+            checks: Vec::new(),
+        }
+    }
+
+    // records the input for a step, checksum is called for the state after the step
+    pub fn record(&mut self, bits: u32, checksum: impl FnOnce() -> u64)
+    {
+        self.inputs.push(bits);
+
+        let step = self.inputs.len() as u32;
+        if step % CHECK_EVERY == 0
+        {
+            self.checks.push((step, checksum()));
+        }
+    }
+
+    // the game is over, the last checksum is always kept
+    pub fn finish(self, source: Source, cleared: bool, score: u32, time: u32, checksum: u64)
+        -> Replay
+    {
+        let step = self.inputs.len() as u32;
+        let mut checks = self.checks;
+        if checks.last().map(|c| c.0) != Some(step)
+        {
+            checks.push((step, checksum));
+        }
+
+        Replay
+        {
+            source: source,
+            seed: self.seed,
+            creep: self.creep,
+            time_limit: self.time_limit,
+
+            cleared: cleared,
+            score: score,
+            time: time,
+
+            inputs: self.inputs,
+            checks: checks,
+        }
+    }
+}
+
+// feeds a replay back into the game, a step at a time
+pub struct Playback
+{
+    replay: Replay,
+    step: usize,
+}
+
+impl Playback
+{
+    pub fn new(replay: Replay) -> Playback
+    {
+        Playback
+        {
+            replay: replay,
+            step: 0,
+        }
+    }
+
+    pub fn replay(&self) -> &Replay
+    {
+        &self.replay
+    }
+
+    pub fn restart(&mut self)
+    {
+        self.step = 0;
+    }
+
+    // the input for the next step, or None if the replay has run out
+    pub fn next_input(&mut self) -> Option<u32>
+    {
+        let bits = self.replay.inputs.get(self.step).copied();
+        if bits.is_some()
+        {
+            self.step += 1;
+        }
+        bits
+    }
+
+    // compares the state after the last step with the recording,
+    // checksum is only called if there's a check for this step
+    pub fn check(&self, checksum: impl FnOnce() -> u64) -> Result<(), String>
+    {
+        let step = self.step as u32;
+
+        match self.replay.checks.binary_search_by_key(&step, |c| c.0)
+        {
+            Ok(i) =>
+            {
+                let expected = self.replay.checks[i].1;
+                let actual = checksum();
+                if actual == expected
+                {
+                    Ok(())
+                }
+                else
+                {
+                    Err(format!("replay drifted at step {} (expected {:016x}, got {:016x})",
+                        step, expected, actual))
+                }
+            },
+            Err(_) => Ok(()),
+        }
+    }
+
+    // whether every recorded step has been played
+    pub fn finished(&self) -> bool
+    {
+        self.step == self.replay.inputs.len()
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::test_utils::temp_dir;
+
+    fn recorded() -> Replay
+    {
+        let mut recorder = Recorder::new(0x0123_4567_89ab_cdef, Some(Creep::Hits(6)), Some(90));
+        // the checksum after step n is n * 7
+        for step in 1..=200u32
+        {
+            recorder.record(if step < 50 { 0 } else { step % 3 }, || u64::from(step) * 7);
+        }
+        recorder.finish(Source::Level("01".to_string(), 0xfeed), true, 1234, 42, 200 * 7)
+    }
+
+    #[test]
+    fn a_written_replay_reads_back_the_same()
+    {
+        let dir = temp_dir("round-trip");
+        let replay = recorded();
+        replay.write(&dir);
+
+        let paths = Replay::list(&dir);
+        assert_eq!(paths.len(), 1);
+        let loaded = Replay::load(&paths[0]).unwrap();
+
+        assert!(matches!(loaded.source, Source::Level(ref id, 0xfeed) if id == "01"));
+        assert_eq!(loaded.seed, replay.seed);
+        assert_eq!(loaded.creep, Some(Creep::Hits(6)));
+        assert_eq!(loaded.time_limit, Some(90));
+        assert_eq!((loaded.cleared, loaded.score, loaded.time), (true, 1234, 42));
+        assert_eq!(loaded.inputs, replay.inputs);
+        assert_eq!(loaded.checks, replay.checks);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn replays_written_at_once_get_their_own_files()
+    {
+        let dir = temp_dir("names");
+        recorded().write(&dir);
+        recorded().write(&dir);
+        assert_eq!(Replay::list(&dir).len(), 2);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn playback_checks_the_recorded_checksums()
+    {
+        let mut playback = Playback::new(recorded());
+
+        for step in 1..=200u64
+        {
+            assert!(playback.next_input().is_some());
+            assert_eq!(playback.check(|| step * 7), Ok(()));
+        }
+        // the last step also has the finishing checksum
+        assert!(playback.finished());
+        assert_eq!(playback.next_input(), None);
+
+        playback.restart();
+        for _ in 0..CHECK_EVERY
+        {
+            playback.next_input();
+        }
+        assert!(playback.check(|| 0).unwrap_err().starts_with("replay drifted at step 60"));
+    }
+
+    #[test]
+    fn a_huge_run_is_an_error()
+    {
+        let src = format!("version {}\nlevel 01 0\nseed 0\ncreep none\ntime none\n\
+            result lose 0 0\ninputs {}x0\n", REPLAY_VERSION, MAX_STEPS + 1);
+        assert!(Replay::parse("huge.txt", &src).is_err());
+    }
+}
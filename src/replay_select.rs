@@ -0,0 +1,334 @@
+use ggez::
+{
+    Context,
+    GameResult,
+    input::
+    {
+        keyboard::KeyCode,
+        mouse::MouseButton,
+    },
+};
+
+use std::path::PathBuf;
+
+use crate::BOARD_WIDTH;
+use crate::
+{
+    state::State,
+    menu::Menu,
+    game::Game,
+    replay::{Replay, Source},
+    daily,
+    shared::Shared,
+    ui::{TextRect, Button, focus::{self, FocusKey}},
+};
+
+const BORDER: f32 = 25.0;
+const BUTTON_BORDER: f32 = 15.0;
+const SIZE: f32 = 35.0;
+
+const REPLAY_BORDER: f32 = 8.0;
+const REPLAY_SIZE: f32 = 20.0;
+const REPLAY_SEP: f32 = 10.0;
+const MESSAGE_SIZE: f32 = 14.0;
+
+const PER_PAGE: usize = 6;
+
+pub struct ReplaySelect
+{
+    title: TextRect,
+    message: Option<TextRect>,
+    replays: Vec<Button>,
+    back: Button,
+
+    prev: Button,
+    next: Button,
+
+    paths: Vec<PathBuf>,
+    // the top of the replay buttons
+    top: f32,
+    page: usize,
+    pages: usize,
+}
+
+impl ReplaySelect
+{
+    // message is shown under the title, for how the last replay went
+    pub fn new(ctx: &mut Context, shared: &Shared, message: Option<String>)
+        -> GameResult<ReplaySelect>
+    {
+        let paths = Replay::list(&Replay::dir(&shared.config_dir));
+        let pages = usize::max(1, paths.len().div_ceil(PER_PAGE));
+
+        let title = TextRect::new(ctx, 0.0, BORDER,
+            ReplaySelect::title_text(0, pages), None, SIZE, None)
+            .center_x(BOARD_WIDTH);
+
+        let message = message.map(|m| TextRect::new(ctx, 0.0, title.rect().bottom() + REPLAY_SEP,
+            m, None, MESSAGE_SIZE, None)
+            .center_x(BOARD_WIDTH));
+
+        let top = title.rect().bottom() + REPLAY_SEP * 2.0 + MESSAGE_SIZE;
+
+        // the buttons below the replays are always in the same place, as if the page were full
+        let replay_h = TextRect::new(ctx, 0.0, 0.0, "Replay", None, REPLAY_SIZE, None)
+            .rect().h + REPLAY_BORDER * 2.0;
+        let bottom = top + PER_PAGE as f32 * replay_h + (PER_PAGE - 1) as f32 * REPLAY_SEP;
+
+        let back_text = TextRect::new(ctx,
+            0.0, bottom + BORDER,
+            "Back", None, SIZE, None)
+            .center_x(BOARD_WIDTH);
+        let back = Button::new(ctx, back_text, BUTTON_BORDER)?;
+
+        let prev_text = TextRect::new(ctx,
+            BORDER + BUTTON_BORDER, bottom + BORDER,
+            "<", None, SIZE, None);
+        let prev = Button::new(ctx, prev_text, BUTTON_BORDER)?;
+
+        let mut next_text = TextRect::new(ctx,
+            0.0, bottom + BORDER,
+            ">", None, SIZE, None);
+        next_text.move_to(
+            BOARD_WIDTH - BORDER - BUTTON_BORDER - next_text.rect().w,
+            next_text.rect().y);
+        let next = Button::new(ctx, next_text, BUTTON_BORDER)?;
+
+        Ok(ReplaySelect
+        {
+            title: title,
+            message: message,
+            replays: ReplaySelect::make_replays(ctx, shared, &paths, 0, top)?,
+            back: back,
+
+            prev: prev,
+            next: next,
+
+            paths: paths,
+            top: top,
+            page: 0,
+            pages: pages,
+        })
+    }
+
+    fn title_text(page: usize, pages: usize) -> String
+    {
+        if pages > 1
+        {
+            format!("Replays ({}/{})", page + 1, pages)
+        }
+        else
+        {
+            "Replays".to_string()
+        }
+    }
+
+    fn page_paths(paths: &[PathBuf], page: usize) -> &[PathBuf]
+    {
+        let first = usize::min(page * PER_PAGE, paths.len());
+        let last = usize::min(first + PER_PAGE, paths.len());
+
+        &paths[first..last]
+    }
+
+    fn make_replays(ctx: &mut Context, shared: &Shared, paths: &[PathBuf], page: usize, y: f32)
+        -> GameResult<Vec<Button>>
+    {
+        let mut v = Vec::new();
+        let mut total_h = 0.0;
+
+        for (i, path) in ReplaySelect::page_paths(paths, page).iter().enumerate()
+        {
+            // the replay is only loaded for its label here, it's loaded again to play it
+            let label = match Replay::load(path)
+            {
+                Ok(r) =>
+                {
+                    let name = match r.source
+                    {
+                        Source::Level(ref id, _) => match shared.levels.find(id)
+                            .and_then(|l| shared.levels.get(l))
+                        {
+                            Some(l) => l.name.clone(),
+                            None => id.clone(),
+                        },
+                        Source::Endless(seed) => format!("Endless {:08x}", seed),
+                        Source::Daily(day) => format!("Daily {}", daily::date(day)),
+                    };
+                    format!("{}: {} in {}s", name, if r.cleared { "clear" } else { "lose" }, r.time)
+                },
+                Err(_) => "(broken replay)".to_string(),
+            };
+
+            let t = TextRect::new(ctx, 0.0, 0.0, label, None, REPLAY_SIZE, None);
+            let mut b = Button::new(ctx, t, REPLAY_BORDER)?;
+
+            let x = BOARD_WIDTH / 2.0 - b.rect().w / 2.0;
+            b.move_to(x, y + total_h + i as f32 * REPLAY_SEP);
+            total_h += b.rect().h;
+
+            v.push(b);
+        }
+
+        Ok(v)
+    }
+
+    fn set_page(&mut self, ctx: &mut Context, shared: &Shared, page: usize) -> GameResult<()>
+    {
+        self.page = page;
+        self.replays = ReplaySelect::make_replays(ctx, shared, &self.paths, page, self.top)?;
+        // keep the title centered
+        self.title.change_text(ctx, ReplaySelect::title_text(self.page, self.pages));
+        let title = self.title.rect();
+        self.title.move_to(BOARD_WIDTH / 2.0 - title.w / 2.0, title.y);
+
+        Ok(())
+    }
+
+    fn set_message(&mut self, ctx: &mut Context, message: String)
+    {
+        self.message = Some(TextRect::new(ctx, 0.0, self.title.rect().bottom() + REPLAY_SEP,
+            message, None, MESSAGE_SIZE, None)
+            .center_x(BOARD_WIDTH));
+    }
+
+    pub fn update(&mut self, ctx: &mut Context, shared: &mut Shared) -> GameResult<Option<State>>
+    {
+        let mut clicked = None;
+        for (b, path) in self.replays.iter().zip(ReplaySelect::page_paths(&self.paths, self.page))
+        {
+            if b.click()
+            {
+                clicked = Some(path.clone());
+            }
+        }
+
+        // a replay that can't be played is reported here rather than ending the game
+        if let Some(path) = clicked
+        {
+            for b in self.replays.iter_mut()
+            {
+                b.reset();
+            }
+
+            match Replay::load(&path).and_then(|r| Game::replay(ctx, shared, r))
+            {
+                Ok(game) => return Ok(Some(State::Game(game))),
+                Err(e) => self.set_message(ctx, format!("Error: {}", e)),
+            }
+        }
+
+        // the page buttons are reset after use, so holding them down only changes
+        // the page once
+        if self.prev.click()
+        {
+            self.prev.reset();
+            if self.page > 0
+            {
+                self.set_page(ctx, shared, self.page - 1)?;
+            }
+        }
+        if self.next.click()
+        {
+            self.next.reset();
+            if self.page + 1 < self.pages
+            {
+                self.set_page(ctx, shared, self.page + 1)?;
+            }
+        }
+
+        if self.back.click()
+        {
+            return Ok(Some(State::Menu(Menu::new(ctx)?)))
+        }
+
+        Ok(None)
+    }
+
+    // the buttons in the order the keyboard goes through them, the page buttons
+    // only while they're shown
+    fn buttons(&mut self) -> Vec<&mut Button>
+    {
+        let mut v = self.replays.iter_mut().collect::<Vec<_>>();
+        if self.pages > 1
+        {
+            v.push(&mut self.prev);
+            v.push(&mut self.next);
+        }
+        v.push(&mut self.back);
+        v
+    }
+
+    // escape goes back to the main menu
+    pub fn key_down(&mut self, key: KeyCode, repeat: bool)
+    {
+        if focus::key_down(&mut self.buttons(), key, repeat) == FocusKey::Back
+        {
+            self.back.press();
+        }
+    }
+
+    pub fn key_up(&mut self, key: KeyCode)
+    {
+        focus::key_up(&mut self.buttons(), key);
+    }
+
+    pub fn mouse_move(&mut self, x: f32, y: f32)
+    {
+        for b in self.replays.iter_mut()
+        {
+            b.mouse_move(x, y);
+        }
+
+        self.prev.mouse_move(x, y);
+        self.next.mouse_move(x, y);
+        self.back.mouse_move(x, y);
+    }
+
+    pub fn mouse_down(&mut self, button: MouseButton, x: f32, y: f32)
+    {
+        for b in self.replays.iter_mut()
+        {
+            b.mouse_down(button, x, y);
+        }
+
+        if self.pages > 1
+        {
+            self.prev.mouse_down(button, x, y);
+            self.next.mouse_down(button, x, y);
+        }
+        self.back.mouse_down(button, x, y);
+    }
+
+    pub fn mouse_up(&mut self, button: MouseButton)
+    {
+        for b in self.replays.iter_mut()
+        {
+            b.mouse_up(button);
+        }
+
+        self.prev.mouse_up(button);
+        self.next.mouse_up(button);
+        self.back.mouse_up(button);
+    }
+
+    pub fn draw(&mut self, ctx: &mut Context) -> GameResult<()>
+    {
+        self.title.draw(ctx)?;
+        if let Some(ref message) = self.message
+        {
+            message.draw(ctx)?;
+        }
+        for b in self.replays.iter_mut()
+        {
+            b.draw(ctx)?;
+        }
+        // the page buttons are only there if there's more than one page
+        if self.pages > 1
+        {
+            self.prev.draw(ctx)?;
+            self.next.draw(ctx)?;
+        }
+        self.back.draw(ctx)
+    }
+}
@@ -0,0 +1,89 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// a small splitmix64 random number generator. the game needs the same numbers from the
+// same seed on every platform so replays play back the same, so it's written out here
+// and only uses integer maths
+#[derive(Copy, Clone)]
+pub struct Rng
+{
+    state: u64,
+}
+
+impl Rng
+{
+    pub fn new(seed: u64) -> Rng
+    {
+        Rng
+        {
+            state: seed,
+        }
+    }
+
+    // a seed that's different every time, from the clock
+    pub fn random_seed() -> u64
+    {
+        let nanos = SystemTime::now().duration_since(UNIX_EPOCH)
+            .map(|d| d.as_nanos() as u64)
+            .unwrap_or(0);
+
+        Rng::new(nanos).next_u64()
+    }
+
+    pub fn next_u64(&mut self) -> u64
+    {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    // a number in [0, n), n can't be 0
+    pub fn below(&mut self, n: u32) -> u32
+    {
+        (((self.next_u64() >> 32) * n as u64) >> 32) as u32
+    }
+
+    // true with the chance p, in [0, 1]
+    pub fn chance(&mut self, p: f32) -> bool
+    {
+        // 24 bits fit exactly in an f32, so this is the same everywhere
+        let f = (self.next_u64() >> 40) as f32 / (1u32 << 24) as f32;
+        f < p
+    }
+
+    // the state, for checksums
+    pub fn state(&self) -> u64
+    {
+        self.state
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    #[test]
+    fn numbers_match_splitmix64()
+    {
+        // the reference implementation's first numbers from seed 0
+        let mut rng = Rng::new(0);
+        assert_eq!(rng.next_u64(), 0xe220_a839_7b1d_cdaf);
+        assert_eq!(rng.next_u64(), 0x6e78_9e6a_a1b9_65f4);
+        assert_eq!(rng.next_u64(), 0x06c4_5d18_8009_454f);
+    }
+
+    #[test]
+    fn below_and_chance_stay_in_range()
+    {
+        let mut rng = Rng::new(42);
+        for _ in 0..1000
+        {
+            assert!(rng.below(7) < 7);
+            assert!(!rng.chance(0.0));
+            assert!(rng.chance(1.0));
+        }
+    }
+}
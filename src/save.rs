@@ -0,0 +1,366 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+// the name of the save file, in the user's config directory
+const SAVE_FILE: &str = "save.txt";
+// bump this when the format changes, older saves are read as empty
+const SAVE_VERSION: u32 = 1;
+
+// the best results on one level
+#[derive(Copy, Clone, Default)]
+pub struct Record
+{
+    pub cleared: bool,
+    pub best_time: Option<u32>,
+    pub best_score: Option<u32>,
+    pub fewest_lost: Option<u32>,
+}
+
+// the best run of endless mode from one seed
+#[derive(Copy, Clone)]
+pub struct EndlessRecord
+{
+    pub best_score: u32,
+    // the wave that run got to
+    pub wave: u32,
+}
+
+// the one attempt at the daily challenge on one day
+#[derive(Copy, Clone)]
+pub struct DailyRecord
+{
+    // false if it's been started but not finished, then the rest are 0
+    pub finished: bool,
+    pub cleared: bool,
+    pub time: u32,
+    pub score: u32,
+    pub lives: u32,
+}
+
+// the player's progress, keyed by level id. a save file looks like this:
+//
+//     version 1
+//     level 01 1 42 120 0
+//     endless 0a1b2c3d 250 6
+//     daily 20379 clear 95 140 2
+//
+// where each level line is the id, whether it's cleared, the best time, the best score,
+// and the fewest lives lost, with '-' for a missing value. each endless line is a seed
+// that's been played, the best score from it and the wave that run got to. each daily
+// line is the day (counted from 1970), 'clear', 'lose' or 'started' if it was never
+// finished, and the time, score and lives left. a line that can't be read is
+// reported and skipped, so one bad line doesn't lose the rest of the save
+pub struct Save
+{
+    path: PathBuf,
+    levels: HashMap<String, Record>,
+    endless: HashMap<u32, EndlessRecord>,
+    daily: HashMap<u32, DailyRecord>,
+}
+
+impl Save
+{
+    // reads the save in dir, a missing save is empty, and a save from an unknown
+    // version is reported and then treated as empty
+    pub fn load(dir: &Path) -> Save
+    {
+        let path = dir.join(SAVE_FILE);
+        let mut save = Save::empty(path.clone());
+
+        match std::fs::read_to_string(&path)
+        {
+            Ok(src) => if let Err(e) = save.parse(&src)
+            {
+                eprintln!("warning: ignoring save file {}: {}", path.display(), e);
+                save = Save::empty(path);
+            },
+            Err(ref e) if e.kind() == std::io::ErrorKind::NotFound => { },
+            Err(e) => eprintln!("warning: could not read save file {}: {}", path.display(), e),
+        }
+
+        save
+    }
+
+    fn empty(path: PathBuf) -> Save
+    {
+        Save
+        {
+            path: path,
+            levels: HashMap::new(),
+            endless: HashMap::new(),
+            daily: HashMap::new(),
+        }
+    }
+
+    fn parse(&mut self, src: &str) -> Result<(), String>
+    {
+        let mut lines = src.lines().enumerate();
+
+        match lines.next().map(|(_, l)| l.split_whitespace().collect::<Vec<_>>())
+        {
+            Some(ref v) if v.len() == 2 && v[0] == "version" =>
+            {
+                if v[1] != SAVE_VERSION.to_string()
+                {
+                    return Err(format!("unsupported version {}", v[1]))
+                }
+            },
+            _ => return Err("missing version".to_string()),
+        }
+
+        for (i, line) in lines
+        {
+            if let Err(e) = self.parse_line(line)
+            {
+                eprintln!("warning: skipping line {} of save file {}: {}",
+                    i + 1, self.path.display(), e);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn parse_line(&mut self, line: &str) -> Result<(), String>
+    {
+        let number = |s: &str| -> Result<u32, String>
+        {
+            s.parse().map_err(|_| format!("invalid value '{}'", s))
+        };
+        let value = |s: &str| -> Result<Option<u32>, String>
+        {
+            if s == "-"
+            {
+                Ok(None)
+            }
+            else
+            {
+                number(s).map(Some)
+            }
+        };
+
+        let v = line.split_whitespace().collect::<Vec<_>>();
+        match v.first()
+        {
+            None => { },
+            Some(&"level") if v.len() == 6 =>
+            {
+                self.levels.insert(v[1].to_string(), Record
+                {
+                    cleared: value(v[2])? == Some(1),
+                    best_time: value(v[3])?,
+                    best_score: value(v[4])?,
+                    fewest_lost: value(v[5])?,
+                });
+            },
+            Some(&"endless") if v.len() == 4 =>
+            {
+                let seed = u32::from_str_radix(v[1], 16)
+                    .map_err(|_| format!("invalid seed '{}'", v[1]))?;
+
+                self.endless.insert(seed, EndlessRecord
+                {
+                    best_score: number(v[2])?,
+                    wave: number(v[3])?,
+                });
+            },
+            Some(&"daily") if v.len() == 6 =>
+            {
+                let (finished, cleared) = match v[2]
+                {
+                    "clear" => (true, true),
+                    "lose" => (true, false),
+                    "started" => (false, false),
+                    r => return Err(format!("unknown result '{}'", r)),
+                };
+
+                self.daily.insert(number(v[1])?, DailyRecord
+                {
+                    finished: finished,
+                    cleared: cleared,
+                    time: number(v[3])?,
+                    score: number(v[4])?,
+                    lives: number(v[5])?,
+                });
+            },
+            Some(_) => return Err(format!("unexpected line '{}'", line)),
+        }
+
+        Ok(())
+    }
+
+    // writes the save, failing to save is reported but doesn't stop the game
+    pub fn write(&self)
+    {
+        let value = |v: Option<u32>| match v
+        {
+            Some(v) => v.to_string(),
+            None => "-".to_string(),
+        };
+
+        // sorted so the file doesn't shuffle around between saves
+        let mut ids = self.levels.keys().collect::<Vec<_>>();
+        ids.sort();
+
+        let mut out = format!("version {}\n", SAVE_VERSION);
+        for id in ids
+        {
+            let r = &self.levels[id];
+            out += &format!("level {} {} {} {} {}\n",
+                id,
+                if r.cleared { 1 } else { 0 },
+                value(r.best_time),
+                value(r.best_score),
+                value(r.fewest_lost));
+        }
+        let mut seeds = self.endless.keys().collect::<Vec<_>>();
+        seeds.sort();
+        for seed in seeds
+        {
+            let r = &self.endless[seed];
+            out += &format!("endless {:08x} {} {}\n", seed, r.best_score, r.wave);
+        }
+        let mut days = self.daily.keys().collect::<Vec<_>>();
+        days.sort();
+        for day in days
+        {
+            let r = &self.daily[day];
+            out += &format!("daily {} {} {} {} {}\n",
+                day,
+                if !r.finished { "started" } else if r.cleared { "clear" } else { "lose" },
+                r.time,
+                r.score,
+                r.lives);
+        }
+
+        let result = match self.path.parent()
+        {
+            Some(dir) => std::fs::create_dir_all(dir),
+            None => Ok(()),
+        }.and_then(|_| std::fs::write(&self.path, out));
+
+        if let Err(e) = result
+        {
+            eprintln!("warning: could not write save file {}: {}", self.path.display(), e);
+        }
+    }
+
+    pub fn record(&self, id: &str) -> Record
+    {
+        self.levels.get(id).copied().unwrap_or_default()
+    }
+
+    // a level was cleared, keep whichever results are better
+    pub fn record_clear(&mut self, id: &str, time: u32, score: u32, lost: u32)
+    {
+        let r = self.levels.entry(id.to_string()).or_default();
+
+        r.cleared = true;
+        r.best_time = Some(r.best_time.map_or(time, |t| u32::min(t, time)));
+        r.best_score = Some(r.best_score.map_or(score, |s| u32::max(s, score)));
+        r.fewest_lost = Some(r.fewest_lost.map_or(lost, |l| u32::min(l, lost)));
+    }
+
+    // a level was lost, only the score counts
+    pub fn record_lose(&mut self, id: &str, score: u32)
+    {
+        let r = self.levels.entry(id.to_string()).or_default();
+
+        r.best_score = Some(r.best_score.map_or(score, |s| u32::max(s, score)));
+    }
+
+    // the seeds of endless mode that have been played, best score first
+    pub fn endless_records(&self) -> Vec<(u32, EndlessRecord)>
+    {
+        let mut records = self.endless.iter().map(|(s, r)| (*s, *r)).collect::<Vec<_>>();
+        records.sort_by(|a, b| b.1.best_score.cmp(&a.1.best_score).then(a.0.cmp(&b.0)));
+        records
+    }
+
+    // a run of endless mode is over, it's kept if it's the best from its seed
+    pub fn record_endless(&mut self, seed: u32, score: u32, wave: u32)
+    {
+        let r = self.endless.entry(seed).or_insert(EndlessRecord
+        {
+            best_score: 0,
+            wave: 0,
+        });
+
+        if score >= r.best_score
+        {
+            r.best_score = score;
+            r.wave = wave;
+        }
+    }
+
+    pub fn daily(&self, day: u32) -> Option<DailyRecord>
+    {
+        self.daily.get(&day).copied()
+    }
+
+    // every day the daily challenge was played, newest first
+    pub fn daily_records(&self) -> Vec<(u32, DailyRecord)>
+    {
+        let mut records = self.daily.iter().map(|(d, r)| (*d, *r)).collect::<Vec<_>>();
+        records.sort_by_key(|r| std::cmp::Reverse(r.0));
+        records
+    }
+
+    // the day's one attempt has started, it counts even if it's never finished
+    pub fn start_daily(&mut self, day: u32)
+    {
+        self.daily.insert(day, DailyRecord
+        {
+            finished: false,
+            cleared: false,
+            time: 0,
+            score: 0,
+            lives: 0,
+        });
+    }
+
+    pub fn record_daily(&mut self, day: u32, cleared: bool, time: u32, score: u32, lives: u32)
+    {
+        self.daily.insert(day, DailyRecord
+        {
+            finished: true,
+            cleared: cleared,
+            time: time,
+            score: score,
+            lives: lives,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    fn parse(src: &str) -> Result<Save, String>
+    {
+        let mut save = Save::empty(PathBuf::from("save.txt"));
+        save.parse(src).map(|_| save)
+    }
+
+    #[test]
+    fn a_bad_line_is_skipped_and_the_rest_kept()
+    {
+        let save = parse("version 1\n\
+            level 01 1 42 120 0\n\
+            level my level 1 42 120 0\n\
+            endless zz 1 1\n\
+            daily 20379 clear 95 140 2\n").unwrap();
+
+        assert!(save.record("01").cleared);
+        assert_eq!(save.record("01").best_time, Some(42));
+        assert!(save.endless_records().is_empty());
+        assert!(save.daily(20379).is_some_and(|r| r.cleared));
+    }
+
+    #[test]
+    fn a_save_from_another_version_is_an_error()
+    {
+        assert!(parse("version 9\nlevel 01 0 - 30 -\n").is_err());
+        assert!(parse("level 01 0 - 30 -\n").is_err());
+    }
+}
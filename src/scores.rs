@@ -0,0 +1,441 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use crate::daily;
+use crate::levels::{self, Levels};
+
+// the name of the high score file, in the user's config directory
+const SCORES_FILE: &str = "scores.txt";
+// bump this when the format changes, older files are read as empty
+const SCORES_VERSION: u32 = 1;
+
+// how many scores each table keeps
+pub const TABLE_SIZE: usize = 10;
+// the longest a name can be
+pub const MAX_NAME: usize = 12;
+
+// which table a score goes in. every level has one, and one more for each mix of
+// creep and time attack it's been played with, since those score differently.
+// endless mode has one for each seed, and the daily challenge one for each day
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub enum Table
+{
+    Level(String, Modes),
+    Endless(u32, Modes),
+    Daily(u32),
+}
+
+// the modes from the settings a run was played in
+#[derive(Copy, Clone, Default, PartialEq, Eq, Hash)]
+pub struct Modes
+{
+    pub creep: bool,
+    pub time_attack: bool,
+}
+
+impl Modes
+{
+    // the modes as they're written in table keys, after a '+' each
+    fn names(self) -> Vec<&'static str>
+    {
+        let mut names = Vec::new();
+        if self.creep
+        {
+            names.push("creep");
+        }
+        if self.time_attack
+        {
+            names.push("time_attack");
+        }
+        names
+    }
+
+    // ' (creep, time attack)' for the tables' names, or nothing without any modes
+    fn label(self) -> String
+    {
+        match self.names().as_slice()
+        {
+            [] => String::new(),
+            names => format!(" ({})", names.join(", ").replace('_', " ")),
+        }
+    }
+}
+
+impl Table
+{
+    pub fn name(&self, levels: &Levels) -> String
+    {
+        match self
+        {
+            Table::Level(id, modes) => match levels.find(id).and_then(|l| levels.get(l))
+            {
+                Some(l) => format!("{}{}", l.name, modes.label()),
+                None => format!("{}{}", id, modes.label()),
+            },
+            Table::Endless(seed, modes) => format!("Endless, seed {:08x}{}", seed, modes.label()),
+            Table::Daily(day) => format!("Daily Challenge {}", daily::date(*day)),
+        }
+    }
+
+    // like 'level:01', 'level:01+creep+time_attack', 'endless:0a1b2c3d' or 'daily:20379'
+    fn key(&self) -> String
+    {
+        let (key, modes) = match self
+        {
+            Table::Level(id, modes) => (format!("level:{}", id), *modes),
+            Table::Endless(seed, modes) => (format!("endless:{:08x}", seed), *modes),
+            Table::Daily(day) => (format!("daily:{}", day), Modes::default()),
+        };
+
+        modes.names().iter().fold(key, |key, name| key + "+" + name)
+    }
+
+    fn from_key(key: &str) -> Option<Table>
+    {
+        let mut parts = key.split('+');
+        let (kind, value) = parts.next()?.split_once(':')?;
+
+        let mut modes = Modes::default();
+        for name in parts
+        {
+            match name
+            {
+                "creep" if !modes.creep => modes.creep = true,
+                "time_attack" if !modes.time_attack => modes.time_attack = true,
+                _ => return None,
+            }
+        }
+
+        match kind
+        {
+            "level" if levels::valid_id(value) => Some(Table::Level(value.to_string(), modes)),
+            "endless" => u32::from_str_radix(value, 16).ok().map(|seed| Table::Endless(seed, modes)),
+            "daily" if modes == Modes::default() => value.parse().ok().map(Table::Daily),
+            _ => None,
+        }
+    }
+
+    // the order tables are shown in, the levels in order with their modes after them,
+    // then endless mode by seed, then the daily challenge by day
+    fn order(&self, levels: &Levels) -> (u32, u32, String)
+    {
+        match self
+        {
+            Table::Level(id, _) => (0, levels.find(id).unwrap_or(u32::MAX), self.key()),
+            Table::Endless(seed, _) => (1, *seed, self.key()),
+            Table::Daily(day) => (2, *day, self.key()),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct Entry
+{
+    pub score: u32,
+    pub time: u32,
+    pub name: String,
+}
+
+// the best scores, a score file looks like this:
+//
+//     version 1
+//     score level:01 1234 95 alice
+//     score level:01+creep 1502 130 alice
+//     score endless:0a1b2c3d 5210 412 bob
+//
+// where each score line is the table, the score, the time and the name, which is
+// the rest of the line
+pub struct HighScores
+{
+    path: PathBuf,
+    tables: HashMap<Table, Vec<Entry>>,
+}
+
+impl HighScores
+{
+    // reads the scores in dir, a missing file is empty, a file that isn't a score
+    // file is reported and then treated as empty, and a broken line is reported and
+    // skipped
+    pub fn load(dir: &Path) -> HighScores
+    {
+        let path = dir.join(SCORES_FILE);
+        let mut scores = HighScores::empty(path.clone());
+
+        match std::fs::read_to_string(&path)
+        {
+            Ok(src) => if let Err(e) = scores.parse(&src)
+            {
+                eprintln!("warning: ignoring high score file {}: {}", path.display(), e);
+                scores = HighScores::empty(path);
+            },
+            Err(ref e) if e.kind() == std::io::ErrorKind::NotFound => { },
+            Err(e) => eprintln!("warning: could not read high score file {}: {}",
+                path.display(), e),
+        }
+
+        scores
+    }
+
+    fn empty(path: PathBuf) -> HighScores
+    {
+        HighScores
+        {
+            path: path,
+            tables: HashMap::new(),
+        }
+    }
+
+    fn parse(&mut self, src: &str) -> Result<(), String>
+    {
+        let mut lines = src.lines().enumerate();
+
+        match lines.next().map(|(_, l)| l.split_whitespace().collect::<Vec<_>>())
+        {
+            Some(ref v) if v.len() == 2 && v[0] == "version" =>
+            {
+                if v[1] != SCORES_VERSION.to_string()
+                {
+                    return Err(format!("unsupported version {}", v[1]))
+                }
+            },
+            _ => return Err("missing version".to_string()),
+        }
+
+        for (i, line) in lines
+        {
+            if let Err(e) = self.parse_line(line)
+            {
+                eprintln!("warning: skipping line {} of high score file {}: {}",
+                    i + 1, self.path.display(), e);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn parse_line(&mut self, line: &str) -> Result<(), String>
+    {
+        if line.trim().is_empty()
+        {
+            return Ok(())
+        }
+
+        let number = |s: &str| -> Result<u32, String>
+        {
+            s.parse().map_err(|_| format!("invalid value '{}'", s))
+        };
+
+        // the name can have spaces in it, so it's everything after the time
+        let v = line.splitn(5, ' ').collect::<Vec<_>>();
+        if v.len() != 5 || v[0] != "score"
+        {
+            return Err(format!("unexpected line '{}'", line))
+        }
+
+        let table = Table::from_key(v[1])
+            .ok_or_else(|| format!("unknown table '{}'", v[1]))?;
+        self.insert(table, Entry
+        {
+            score: number(v[2])?,
+            time: number(v[3])?,
+            name: clean_name(v[4]),
+        });
+
+        Ok(())
+    }
+
+    // writes the scores, failing to save is reported but doesn't stop the game
+    pub fn write(&self)
+    {
+        // sorted so the file doesn't shuffle around between saves
+        let mut tables = self.tables.iter()
+            .map(|(t, entries)| (t.key(), entries))
+            .collect::<Vec<_>>();
+        tables.sort_by(|a, b| a.0.cmp(&b.0));
+
+        let mut out = format!("version {}\n", SCORES_VERSION);
+        for (key, entries) in tables
+        {
+            for e in entries
+            {
+                out += &format!("score {} {} {} {}\n", key, e.score, e.time, e.name);
+            }
+        }
+
+        let result = match self.path.parent()
+        {
+            Some(dir) => std::fs::create_dir_all(dir),
+            None => Ok(()),
+        }.and_then(|_| std::fs::write(&self.path, out));
+
+        if let Err(e) = result
+        {
+            eprintln!("warning: could not write high score file {}: {}", self.path.display(), e);
+        }
+    }
+
+    // every level's table, in order, and every other table with scores in it
+    pub fn tables(&self, levels: &Levels) -> Vec<Table>
+    {
+        let mut tables = (1..=levels.count())
+            .filter_map(|l| levels.get(l))
+            .map(|l| Table::Level(l.id.clone(), Modes::default()))
+            .collect::<Vec<_>>();
+        for table in self.tables.keys()
+        {
+            if !tables.contains(table)
+            {
+                tables.push(table.clone());
+            }
+        }
+
+        tables.sort_by_cached_key(|t| t.order(levels));
+        tables
+    }
+
+    // the scores in a table, best first
+    pub fn table(&self, table: &Table) -> &[Entry]
+    {
+        self.tables.get(table).map_or(&[], |v| v.as_slice())
+    }
+
+    // adds a score to a table, if it's good enough, and gives where it placed.
+    // higher scores come first, then faster times, then whoever got there first
+    pub fn insert(&mut self, table: Table, entry: Entry) -> Option<usize>
+    {
+        let entries = self.tables.entry(table).or_default();
+
+        let rank = entries.iter()
+            .position(|e| (entry.score, std::cmp::Reverse(entry.time))
+                > (e.score, std::cmp::Reverse(e.time)))
+            .unwrap_or(entries.len());
+
+        if rank >= TABLE_SIZE
+        {
+            return None
+        }
+
+        entries.insert(rank, entry);
+        entries.truncate(TABLE_SIZE);
+
+        Some(rank)
+    }
+
+    // changes the name on a score that was just put in
+    pub fn rename(&mut self, table: &Table, rank: usize, name: &str)
+    {
+        if let Some(e) = self.tables.get_mut(table).and_then(|t| t.get_mut(rank))
+        {
+            e.name = clean_name(name);
+        }
+    }
+}
+
+// names are one line, without spaces at the ends, and never empty
+pub fn clean_name(name: &str) -> String
+{
+    let name = name.chars()
+        .filter(|c| !c.is_control())
+        .take(MAX_NAME)
+        .collect::<String>();
+
+    match name.trim()
+    {
+        "" => "???".to_string(),
+        name => name.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    fn parse(src: &str) -> Result<HighScores, String>
+    {
+        let mut scores = HighScores::empty(PathBuf::from("scores.txt"));
+        scores.parse(src).map(|_| scores)
+    }
+
+    fn level(id: &str) -> Table
+    {
+        Table::Level(id.to_string(), Modes::default())
+    }
+
+    #[test]
+    fn a_bad_line_is_skipped_and_the_rest_kept()
+    {
+        let scores = parse("version 1\n\
+            score level:01 1234 95 alice\n\
+            score level:my level 99 1 bob\n\
+            score level: 99 1 carol\n\
+            score level:01+fast 99 1 carol\n\
+            score endless:0a1b2c3d lots 1 dave\n\
+            score endless:0a1b2c3d 5210 412 erin the 2nd\n").unwrap();
+
+        let level = scores.table(&level("01"));
+        assert_eq!(level.len(), 1);
+        assert_eq!(level[0].name, "alice");
+
+        let endless = scores.table(&Table::Endless(0x0a1b_2c3d, Modes::default()));
+        assert_eq!(endless.len(), 1);
+        assert_eq!(endless[0].score, 5210);
+        assert_eq!(endless[0].name, "erin the 2nd");
+
+        assert_eq!(scores.tables.len(), 2);
+    }
+
+    #[test]
+    fn every_table_key_reads_back_as_the_same_table()
+    {
+        let both = Modes
+        {
+            creep: true,
+            time_attack: true,
+        };
+        let tables = vec![
+            level("01"),
+            Table::Level("bonus_round-2".to_string(), both),
+            Table::Endless(0xdead_beef, Modes { creep: true, time_attack: false }),
+            Table::Daily(20_379),
+        ];
+        for table in tables
+        {
+            assert!(Table::from_key(&table.key()) == Some(table.clone()), "{}", table.key());
+        }
+
+        assert_eq!(Table::Level("01".to_string(), both).key(), "level:01+creep+time_attack");
+        assert!(Table::from_key("level:01+creep+creep").is_none());
+        assert!(Table::from_key("daily:20379+creep").is_none());
+    }
+
+    #[test]
+    fn modes_and_seeds_get_tables_of_their_own()
+    {
+        let mut scores = HighScores::empty(PathBuf::from("scores.txt"));
+        let entry = |score| Entry
+        {
+            score: score,
+            time: 10,
+            name: "someone".to_string(),
+        };
+        let creep = Modes
+        {
+            creep: true,
+            ..Modes::default()
+        };
+
+        assert_eq!(scores.insert(level("01"), entry(100)), Some(0));
+        assert_eq!(scores.insert(Table::Level("01".to_string(), creep), entry(50)), Some(0));
+        assert_eq!(scores.insert(Table::Endless(1, Modes::default()), entry(10)), Some(0));
+        assert_eq!(scores.insert(Table::Endless(2, Modes::default()), entry(5)), Some(0));
+        assert_eq!(scores.table(&level("01")).len(), 1);
+    }
+
+    #[test]
+    fn a_file_without_the_version_is_an_error()
+    {
+        assert!(parse("score endless:0a1b2c3d 5210 412 erin\n").is_err());
+        assert!(parse("version 9\n").is_err());
+    }
+}
@@ -0,0 +1,307 @@
+use std::path::{Path, PathBuf};
+
+use crate::scores;
+use crate::input::{self, Action, Bindings, MouseControl};
+
+// the name of the settings file, in the user's config directory
+const SETTINGS_FILE: &str = "settings.txt";
+
+// the player's settings. a settings file has one 'key = value' per line,
+// and lines starting with '#' are comments. a '#' anywhere else is part of the
+// value, so names can have one
+pub struct Settings
+{
+    path: PathBuf,
+
+    // levels unlock one at a time, as the one before is cleared
+    pub campaign: bool,
+    // every level is unlocked, whatever the campaign says (for testing)
+    pub unlock_all: bool,
+    // the bricks creep down towards the paddle in every level
+    pub creep: bool,
+    // every level has a time limit
+    pub time_attack: bool,
+    // the name put on new high scores
+    pub name: String,
+    // the keys for each action, as 'bind_<action> = <key> <key> ...'
+    pub bindings: Bindings,
+    // whether the mouse controls the paddle
+    pub mouse: MouseControl,
+
+    // set from the command line, these aren't saved
+    arg_unlock_all: bool,
+    arg_creep: bool,
+    arg_time_attack: bool,
+}
+
+impl Settings
+{
+    // reads the settings in dir, a missing file is written out with the defaults,
+    // and broken lines are reported and skipped
+    pub fn load(dir: &Path) -> Settings
+    {
+        let mut settings = Settings
+        {
+            path: dir.join(SETTINGS_FILE),
+
+            campaign: true,
+            unlock_all: false,
+            creep: false,
+            time_attack: false,
+            name: "player".to_string(),
+            bindings: Bindings::default(),
+            mouse: MouseControl::Off,
+
+            arg_unlock_all: false,
+            arg_creep: false,
+            arg_time_attack: false,
+        };
+
+        match std::fs::read_to_string(&settings.path)
+        {
+            Ok(src) => settings.parse(&src),
+            Err(ref e) if e.kind() == std::io::ErrorKind::NotFound => settings.write(),
+            Err(e) => eprintln!("warning: could not read settings file {}: {}",
+                settings.path.display(), e),
+        }
+
+        settings
+    }
+
+    fn parse(&mut self, src: &str)
+    {
+        for (i, line) in src.lines().enumerate()
+        {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#')
+            {
+                continue;
+            }
+
+            let warn = |msg: String|
+            {
+                eprintln!("warning: {}:{}: {}", self.path.display(), i + 1, msg);
+            };
+
+            let (key, value) = match line.find('=')
+            {
+                Some(c) => (line[..c].trim(), line[c + 1..].trim()),
+                None =>
+                {
+                    warn(format!("expected 'key = value', found '{}'", line));
+                    continue;
+                },
+            };
+
+            let flag = |value: &str| match value
+            {
+                "true" => Some(true),
+                "false" => Some(false),
+                _ =>
+                {
+                    warn(format!("expected true or false for '{}', found '{}'", key, value));
+                    None
+                },
+            };
+
+            match key
+            {
+                "campaign" => if let Some(v) = flag(value) { self.campaign = v },
+                "unlock_all" => if let Some(v) = flag(value) { self.unlock_all = v },
+                "creep" => if let Some(v) = flag(value) { self.creep = v },
+                "time_attack" => if let Some(v) = flag(value) { self.time_attack = v },
+                "name" => self.name = scores::clean_name(value),
+                "mouse" => match MouseControl::from_name(value)
+                {
+                    Some(m) => self.mouse = m,
+                    None => warn(format!("expected off, speed or direct for 'mouse', found '{}'",
+                        value)),
+                },
+                _ => match key.strip_prefix("bind_").and_then(Action::from_name)
+                {
+                    Some(action) =>
+                    {
+                        let mut keys = Vec::new();
+                        for name in value.split_whitespace()
+                        {
+                            match input::key_from_name(name)
+                            {
+                                Some(k) => keys.push(k),
+                                None => warn(format!("unknown key '{}' for '{}'", name, key)),
+                            }
+                        }
+                        self.bindings.set_keys(action, keys);
+                    },
+                    None => warn(format!("unknown setting '{}'", key)),
+                },
+            }
+        }
+    }
+
+    // the command line can override some settings, without saving them
+    pub fn apply_args(&mut self, args: impl Iterator<Item = String>)
+    {
+        for arg in args
+        {
+            match arg.as_str()
+            {
+                "--unlock-all" => self.arg_unlock_all = true,
+                "--creep" => self.arg_creep = true,
+                "--time-attack" => self.arg_time_attack = true,
+                _ => { },
+            }
+        }
+    }
+
+    // whether the levels should all be unlocked, from the settings or the command line
+    pub fn all_unlocked(&self) -> bool
+    {
+        self.unlock_all || self.arg_unlock_all
+    }
+
+    // whether creep mode is on, from the settings or the command line
+    pub fn creep_mode(&self) -> bool
+    {
+        self.creep || self.arg_creep
+    }
+
+    // whether time attack mode is on, from the settings or the command line
+    pub fn time_attack_mode(&self) -> bool
+    {
+        self.time_attack || self.arg_time_attack
+    }
+
+    // writes the settings, failing to save is reported but doesn't stop the game
+    pub fn write(&self)
+    {
+        let mut out = format!(
+            "# levels unlock one at a time\n\
+            campaign = {}\n\
+            # unlock every level anyway\n\
+            unlock_all = {}\n\
+            # the bricks creep down in every level\n\
+            creep = {}\n\
+            # every level has a time limit\n\
+            time_attack = {}\n\
+            # the name put on new high scores\n\
+            name = {}\n\
+            # the mouse moves the paddle: off, speed (at the paddle's speed) or direct\n\
+            mouse = {}\n",
+            self.campaign,
+            self.unlock_all,
+            self.creep,
+            self.time_attack,
+            self.name,
+            self.mouse.name());
+
+        out += "# the keys for each action, any number of them\n";
+        for action in Action::ALL.iter()
+        {
+            let keys = self.bindings.keys(*action).iter()
+                .map(|k| input::key_name(*k))
+                .collect::<Vec<_>>();
+            out += &format!("bind_{} = {}\n", action.name(), keys.join(" "));
+        }
+
+        let result = match self.path.parent()
+        {
+            Some(dir) => std::fs::create_dir_all(dir),
+            None => Ok(()),
+        }.and_then(|_| std::fs::write(&self.path, out));
+
+        if let Err(e) = result
+        {
+            eprintln!("warning: could not write settings file {}: {}", self.path.display(), e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::test_utils::temp_dir;
+    use ggez::input::keyboard::KeyCode;
+
+    #[test]
+    fn only_a_whole_line_is_a_comment()
+    {
+        let dir = temp_dir("settings-comments");
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join(SETTINGS_FILE), "  # creep = true\nname = #1 fan\ncampaign = false\n").unwrap();
+
+        let settings = Settings::load(&dir);
+        assert!(!settings.creep);
+        assert!(!settings.campaign);
+        assert_eq!(settings.name, "#1 fan");
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn a_name_with_a_hash_is_saved_and_read_back()
+    {
+        let dir = temp_dir("settings-name");
+        let mut settings = Settings::load(&dir);
+        settings.name = "a#b".to_string();
+        settings.write();
+
+        assert_eq!(Settings::load(&dir).name, "a#b");
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn bindings_are_saved_and_read_back()
+    {
+        let dir = temp_dir("settings-bindings");
+        let mut settings = Settings::load(&dir);
+        settings.bindings.set_keys(Action::MoveLeft, vec![KeyCode::Q, KeyCode::Numpad4]);
+        settings.bindings.set_keys(Action::Pause, Vec::new());
+        settings.write();
+
+        let loaded = Settings::load(&dir);
+        for action in Action::ALL.iter()
+        {
+            assert_eq!(loaded.bindings.keys(*action), settings.bindings.keys(*action));
+        }
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn an_action_can_have_several_keys_and_unknown_ones_are_skipped()
+    {
+        let dir = temp_dir("settings-keys");
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join(SETTINGS_FILE),
+            "bind_move_left = Left A
+bind_launch = Space Banana Return
+").unwrap();
+
+        let settings = Settings::load(&dir);
+        assert_eq!(settings.bindings.keys(Action::MoveLeft), [KeyCode::Left, KeyCode::A]);
+        assert_eq!(settings.bindings.keys(Action::Launch), [KeyCode::Space, KeyCode::Return]);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn a_missing_bind_line_keeps_the_default_keys()
+    {
+        let dir = temp_dir("settings-default-keys");
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join(SETTINGS_FILE), "bind_pause = Q
+").unwrap();
+
+        let settings = Settings::load(&dir);
+        let defaults = Bindings::default();
+        assert_eq!(settings.bindings.keys(Action::Pause), [KeyCode::Q]);
+        for action in Action::ALL.iter().filter(|a| **a != Action::Pause)
+        {
+            assert_eq!(settings.bindings.keys(*action), defaults.keys(*action));
+        }
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,82 @@
+use std::path::PathBuf;
+
+use crate::
+{
+    levels::Levels,
+    save::Save,
+    scores::HighScores,
+    settings::Settings,
+};
+
+// the things that last for the whole run, every state gets these
+pub struct Shared
+{
+    pub levels: Levels,
+    pub save: Save,
+    pub scores: HighScores,
+    pub settings: Settings,
+
+    // where the save, settings, and replays go
+    pub config_dir: PathBuf,
+}
+
+impl Shared
+{
+    // with the campaign rule, a level is only unlocked once the one before it is cleared
+    pub fn unlocked(&self, level: u32) -> bool
+    {
+        if level <= 1 || !self.settings.campaign || self.settings.all_unlocked()
+        {
+            return true
+        }
+
+        match self.levels.get(level - 1)
+        {
+            Some(l) => self.save.record(&l.id).cleared,
+            None => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::test_utils::{self, temp_dir};
+
+    #[test]
+    fn a_level_unlocks_once_the_one_before_is_cleared()
+    {
+        let dir = temp_dir("shared-unlocked");
+        let mut shared = Shared
+        {
+            levels: test_utils::levels("shared-unlocked-levels", 3),
+            save: Save::load(&dir),
+            scores: HighScores::load(&dir),
+            settings: Settings::load(&dir),
+            config_dir: dir.clone(),
+        };
+
+        assert!(shared.unlocked(1));
+        assert!(!shared.unlocked(2));
+        assert!(!shared.unlocked(3));
+
+        shared.save.record_clear("01", 30, 100, 0);
+        assert!(shared.unlocked(2));
+        assert!(!shared.unlocked(3));
+
+        shared.settings.unlock_all = true;
+        assert!(shared.unlocked(3));
+
+        shared.settings.unlock_all = false;
+        shared.settings.campaign = false;
+        assert!(shared.unlocked(3));
+
+        // and from the command line
+        shared.settings.campaign = true;
+        shared.settings.apply_args(vec!["--unlock-all".to_string()].into_iter());
+        assert!(shared.unlocked(3));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -5,8 +5,16 @@ use crate::
     game::Game,
     level_clear::LevelClear,
     level_lose::LevelLose,
+    replay_select::ReplaySelect,
+    endless_select::EndlessSelect,
+    daily::Daily,
+    high_scores::HighScores,
+    controls::Controls,
 };
 
+// there's only ever one state, so the game being much bigger than the menus
+// doesn't matter
+#[allow(clippy::large_enum_variant)]
 pub enum State
 {
     // the main menu
@@ -19,4 +27,14 @@ pub enum State
     LevelClear(LevelClear),
     // the level lose screen
     LevelLose(LevelLose),
+    // the list of recorded runs
+    ReplaySelect(ReplaySelect),
+    // starting endless mode
+    EndlessSelect(EndlessSelect),
+    // the daily challenge, and how the past days went
+    Daily(Daily),
+    // the high score tables
+    HighScores(HighScores),
+    // rebinding the keys
+    Controls(Controls),
 }
@@ -0,0 +1,31 @@
+// helpers shared by the tests
+
+use std::path::PathBuf;
+
+use crate::levels::Levels;
+
+// an empty directory for a test to write files in, named after the test and the
+// process so tests running at the same time don't share one
+pub fn temp_dir(name: &str) -> PathBuf
+{
+    let dir = std::env::temp_dir()
+        .join(format!("brick-breaker-{}-{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    dir
+}
+
+// count levels of one brick each, with the ids '01', '02' and so on
+pub fn levels(name: &str, count: u32) -> Levels
+{
+    let dir = temp_dir(name);
+    std::fs::create_dir_all(&dir).unwrap();
+    for i in 1..=count
+    {
+        std::fs::write(dir.join(format!("{:02}.lvl", i)),
+            format!("name: Level {}\nauthor: Someone\n---\n1\n", i)).unwrap();
+    }
+
+    let levels = Levels::load_dir(&dir).unwrap();
+    std::fs::remove_dir_all(&dir).unwrap();
+    levels
+}
@@ -16,14 +16,23 @@ use ggez::
 
 use super::TextRect;
 
+// how far the focus ring is outside the button, and how thick it is
+const RING_GAP: f32 = 3.0;
+const RING_WIDTH: f32 = 2.0;
+
 pub struct Button
 {
     text: TextRect,
     button: Mesh,
+    ring: Mesh,
     rect: Rect,
 
     hover: bool,
     click: bool,
+    // locked buttons are greyed out, and can't be clicked
+    locked: bool,
+    // the focused button is the one the keyboard works, it has a ring around it
+    focused: bool,
 }
 
 impl Button
@@ -46,14 +55,25 @@ impl Button
             text_rect.h + border * 2.0),
             graphics::WHITE)?;
 
+        let ring = Mesh::new_rectangle(
+            ctx,
+            DrawMode::stroke(RING_WIDTH),
+            Rect::new(-RING_GAP, -RING_GAP,
+            rect.w + RING_GAP * 2.0,
+            rect.h + RING_GAP * 2.0),
+            Color::from_rgb(0, 120, 220))?;
+
         Ok(Button
         {
             text: text,
             button: button,
+            ring: ring,
             rect: rect,
 
             hover: false,
             click: false,
+            locked: false,
+            focused: false,
         })
     }
 
@@ -76,26 +96,62 @@ impl Button
         self.click = false;
     }
 
+    pub fn set_locked(&mut self, locked: bool)
+    {
+        self.locked = locked;
+        self.text.set_color(if locked
+        {
+            Color::from_rgb(160, 160, 160)
+        }
+        else
+        {
+            graphics::BLACK
+        });
+    }
+
+    pub fn locked(&self) -> bool
+    {
+        self.locked
+    }
+
+    pub fn focused(&self) -> bool
+    {
+        self.focused
+    }
+
+    pub fn set_focused(&mut self, focused: bool)
+    {
+        self.focused = focused;
+    }
+
     pub fn click(&self) -> bool
     {
         self.click
     }
 
-    pub fn mouse_move(&mut self, x: f32, y: f32)
+    // the keyboard's version of mouse_down and mouse_up, the button stays clicked
+    // until it's released
+    pub fn press(&mut self)
     {
-        if self.rect.contains([x, y])
+        if !self.locked
         {
-            self.hover = true;
-        }
-        else
-        {
-            self.hover = false;
+            self.click = true;
         }
     }
 
+    pub fn release(&mut self)
+    {
+        self.click = false;
+    }
+
+    pub fn mouse_move(&mut self, x: f32, y: f32)
+    {
+        self.hover = self.rect.contains([x, y]);
+    }
+
     pub fn mouse_down(&mut self, button: MouseButton, x: f32, y: f32)
     {
-        if self.rect.contains([x, y]) && button == MouseButton::Left
+        if self.rect.contains([x, y]) && button == MouseButton::Left && !self.locked
         {
             self.click = true;
         }
@@ -109,12 +165,15 @@ impl Button
         }
     }
 
-
     pub fn draw(&self, ctx: &mut Context) -> GameResult<()>
     {
         graphics::draw(ctx, &self.button, DrawParam::new()
             .dest(self.rect.point())
-            .color(if self.click
+            .color(if self.locked
+            {
+                Color::from_rgb(224, 224, 224)
+            }
+            else if self.click
             {
                 Color::from_rgb(64, 64, 64)
             }
@@ -126,6 +185,10 @@ impl Button
             {
                 Color::from_rgb(191, 191, 191)
             }))?;
+        if self.focused
+        {
+            graphics::draw(ctx, &self.ring, DrawParam::new().dest(self.rect.point()))?;
+        }
         self.text.draw(ctx)
     }
 }
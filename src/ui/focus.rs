@@ -0,0 +1,85 @@
+use ggez::input::keyboard::KeyCode;
+
+use super::Button;
+
+// what a key did to a screen's buttons
+#[derive(Copy, Clone, PartialEq, Eq)]
+pub enum FocusKey
+{
+    // it moved the focus or pressed a button
+    Used,
+    // escape, where it goes back to is up to the screen
+    Back,
+    // nothing to do with the buttons
+    Unused,
+}
+
+// moves the focus through buttons with the arrow keys or tab, in the order they're
+// given and skipping locked ones, and presses the focused one with return. nothing
+// is focused to start with, so the first key only shows where the focus is
+pub fn key_down(buttons: &mut [&mut Button], key: KeyCode, repeat: bool) -> FocusKey
+{
+    let current = buttons.iter().position(|b| b.focused());
+
+    match key
+    {
+        KeyCode::Up | KeyCode::Left => move_focus(buttons, current, -1),
+        KeyCode::Down | KeyCode::Right | KeyCode::Tab => move_focus(buttons, current, 1),
+        // holding it down doesn't press anything new
+        KeyCode::Return | KeyCode::NumpadEnter => if !repeat
+        {
+            confirm(buttons);
+        },
+        KeyCode::Escape if !repeat => return FocusKey::Back,
+        _ => return FocusKey::Unused,
+    }
+
+    FocusKey::Used
+}
+
+// presses the focused button, or if nothing's focused yet shows where the focus is
+pub fn confirm(buttons: &mut [&mut Button])
+{
+    match buttons.iter().position(|b| b.focused())
+    {
+        Some(i) => buttons[i].press(),
+        None => move_focus(buttons, None, 1),
+    }
+}
+
+// letting go of return (or escape) lets go of the button, like the mouse button does
+pub fn key_up(buttons: &mut [&mut Button], key: KeyCode)
+{
+    match key
+    {
+        KeyCode::Return | KeyCode::NumpadEnter | KeyCode::Escape => for b in buttons.iter_mut()
+        {
+            b.release();
+        },
+        _ => { },
+    }
+}
+
+fn move_focus(buttons: &mut [&mut Button], current: Option<usize>, dir: isize)
+{
+    let len = buttons.len() as isize;
+    let start = match current
+    {
+        Some(i) => i as isize,
+        None if dir > 0 => -1,
+        None => len,
+    };
+
+    let next = (1..=len)
+        .map(|step| (start + dir * step).rem_euclid(len) as usize)
+        .find(|i| !buttons[*i].locked());
+
+    if let Some(next) = next
+    {
+        for b in buttons.iter_mut()
+        {
+            b.set_focused(false);
+        }
+        buttons[next].set_focused(true);
+    }
+}
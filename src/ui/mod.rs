@@ -1,5 +1,8 @@
 mod text_rect;
 mod button;
+mod text_input;
+pub mod focus;
 
 pub use self::text_rect::TextRect;
 pub use self::button::Button;
+pub use self::text_input::TextInput;
@@ -0,0 +1,168 @@
+use ggez::
+{
+    Context,
+    GameResult,
+    graphics::
+    {
+        self,
+        Rect,
+        Mesh,
+        Color,
+        DrawMode,
+        DrawParam,
+    },
+    input::
+    {
+        keyboard::KeyCode,
+        mouse::MouseButton,
+    },
+};
+
+use super::TextRect;
+
+// the width of the caret at the end of the text
+const CARET_W: f32 = 2.0;
+
+// a box that can be typed in, it takes the characters from text events and handles
+// backspace itself. it only takes input while it's focused
+pub struct TextInput
+{
+    text: TextRect,
+    value: String,
+    max_len: usize,
+
+    bg: Mesh,
+    caret: Mesh,
+    rect: Rect,
+    border: f32,
+
+    focused: bool,
+}
+
+impl TextInput
+{
+    // it's wide enough for max_len of the widest character
+    pub fn new(
+        ctx: &mut Context,
+        x: f32,
+        y: f32,
+        value: &str,
+        max_len: usize,
+        scale: f32,
+        border: f32)
+        -> GameResult<TextInput>
+    {
+        let widest = TextRect::new(ctx, 0.0, 0.0, "W".repeat(max_len), None, scale, None).rect();
+        let rect = Rect::new(x, y, widest.w + CARET_W + border * 2.0, widest.h + border * 2.0);
+
+        let value = value.chars().take(max_len).collect::<String>();
+
+        Ok(TextInput
+        {
+            text: TextRect::new(ctx, x + border, y + border, &value, None, scale, None),
+            value: value,
+            max_len: max_len,
+
+            bg: Mesh::new_rectangle(
+                ctx,
+                DrawMode::fill(),
+                Rect::new(0.0, 0.0, rect.w, rect.h),
+                graphics::WHITE)?,
+            caret: Mesh::new_rectangle(
+                ctx,
+                DrawMode::fill(),
+                Rect::new(0.0, 0.0, CARET_W, widest.h),
+                graphics::BLACK)?,
+            rect: rect,
+            border: border,
+
+            focused: false,
+        })
+    }
+
+    pub fn center_x(mut self, width: f32) -> Self
+    {
+        let y = self.rect.y;
+        self.move_to(width / 2.0 - self.rect.w / 2.0, y);
+
+        self
+    }
+
+    pub fn rect(&self) -> Rect
+    {
+        self.rect
+    }
+
+    pub fn move_to(&mut self, x: f32, y: f32)
+    {
+        self.rect.move_to([x, y]);
+        self.text.move_to(x + self.border, y + self.border);
+    }
+
+    pub fn value(&self) -> &str
+    {
+        &self.value
+    }
+
+    pub fn focused(&self) -> bool
+    {
+        self.focused
+    }
+
+    pub fn set_focused(&mut self, focused: bool)
+    {
+        self.focused = focused;
+    }
+
+    // a character was typed, control characters (like backspace on some platforms)
+    // come through key_down instead
+    pub fn text_input(&mut self, ctx: &mut Context, ch: char)
+    {
+        if self.focused && !ch.is_control() && self.value.chars().count() < self.max_len
+        {
+            self.value.push(ch);
+            self.text.change_text(ctx, &self.value);
+        }
+    }
+
+    pub fn key_down(&mut self, ctx: &mut Context, key: KeyCode)
+    {
+        if self.focused && key == KeyCode::Back && self.value.pop().is_some()
+        {
+            self.text.change_text(ctx, &self.value);
+        }
+    }
+
+    // clicking the box focuses it, clicking anywhere else takes the focus away
+    pub fn mouse_down(&mut self, button: MouseButton, x: f32, y: f32)
+    {
+        if button == MouseButton::Left
+        {
+            self.focused = self.rect.contains([x, y]);
+        }
+    }
+
+    pub fn draw(&self, ctx: &mut Context) -> GameResult<()>
+    {
+        graphics::draw(ctx, &self.bg, DrawParam::new()
+            .dest(self.rect.point())
+            .color(if self.focused
+            {
+                Color::from_rgb(224, 224, 224)
+            }
+            else
+            {
+                Color::from_rgb(191, 191, 191)
+            }))?;
+        self.text.draw(ctx)?;
+
+        if self.focused
+        {
+            let text = self.text.rect();
+            graphics::draw(ctx, &self.caret, DrawParam::new()
+                .dest([text.right(), text.y]))?;
+        }
+
+        Ok(())
+    }
+}
@@ -6,6 +6,7 @@ use ggez::
     {
         self,
         Text,
+        TextFragment,
         Rect,
         Font,
         Scale,
@@ -37,7 +38,7 @@ impl TextRect
         -> TextRect
     {
         let mut t = Text::new(text.to_string());
-        t.set_font(if let Some(f) = font { f } else { Font::default() }, Scale::uniform(scale));
+        t.set_font(font.unwrap_or_default(), Scale::uniform(scale));
 
         let d = t.dimensions(ctx);
 
@@ -46,7 +47,7 @@ impl TextRect
             text: t,
             rect: Rect::new(x, y, d.0 as f32, d.1 as f32),
             color: if let Some(c) = color { c } else { graphics::BLACK },
-            font: if let Some(f) = font { f } else { Font::default() },
+            font: font.unwrap_or_default(),
             scale: Scale::uniform(scale),
         }
     }
@@ -60,6 +61,19 @@ impl TextRect
         self
     }
 
+    // adds another line under the text, in a different size
+    // (change_text will get rid of it)
+    pub fn add_line(mut self, ctx: &mut Context, text: impl ToString, scale: f32) -> Self
+    {
+        self.text.add(TextFragment::new(format!("\n{}", text.to_string()))
+            .scale(Scale::uniform(scale)));
+
+        let d = self.text.dimensions(ctx);
+        self.rect = Rect::new(self.rect.x, self.rect.y, d.0 as f32, d.1 as f32);
+
+        self
+    }
+
     pub fn move_to(&mut self, x: f32, y: f32)
     {
         self.rect.move_to([x, y]);
@@ -76,6 +90,11 @@ impl TextRect
         self.rect = Rect::new(self.rect.x, self.rect.y, d.0 as f32, d.1 as f32);
     }
 
+    pub fn set_color(&mut self, color: Color)
+    {
+        self.color = color;
+    }
+
     pub fn rect(&self) -> Rect
     {
         self.rect
@@ -11,26 +11,65 @@ use ggez::
         },
         mouse::MouseButton,
     },
-    event::EventHandler,
+    event::{EventHandler, Axis, Button as PadButton, GamepadId},
 };
 
 use crate::state::State;
 use crate::menu::Menu;
+use crate::levels::Levels;
+use crate::save::Save;
+use crate::scores::HighScores;
+use crate::settings::Settings;
+use crate::shared::Shared;
+use crate::input::{self, StickKeys};
 
 pub struct Window
 {
     state: State,
+    shared: Shared,
+
+    // the stick as arrow keys, for the menus
+    stick_keys: StickKeys,
 }
 
 impl Window
 {
     pub fn new(ctx: &mut Context) -> GameResult<Window>
     {
+        let config_dir = ggez::filesystem::user_config_dir(ctx).to_path_buf();
+
+        let mut settings = Settings::load(&config_dir);
+        settings.apply_args(std::env::args().skip(1));
+
         Ok(Window
         {
             state: State::Menu(Menu::new(ctx)?),
+            shared: Shared
+            {
+                levels: Levels::load()?,
+                save: Save::load(&config_dir),
+                scores: HighScores::load(&config_dir),
+                settings: settings,
+                config_dir: config_dir,
+            },
+
+            stick_keys: StickKeys::default(),
         })
     }
+
+    // the gamepad works the menus by standing in for the keyboard, except on the
+    // controls screen while a key is being bound
+    fn pad_key(&mut self, ctx: &mut Context, key: KeyCode, down: bool)
+    {
+        match &mut self.state
+        {
+            State::Controls(ref controls) if controls.listening() => { },
+            // the game has the gamepad itself, this is only for the pause menu
+            State::Game(ref mut game) => game.pad_key(key, down),
+            _ if down => self.key_down_event(ctx, key, KeyMods::NONE, false),
+            _ => self.key_up_event(ctx, key, KeyMods::NONE),
+        }
+    }
 }
 
 impl EventHandler for Window
@@ -39,23 +78,43 @@ impl EventHandler for Window
     {
         match &mut self.state
         {
-            State::Menu(ref mut menu) => if let Some(state) = menu.update(ctx)?
+            State::Menu(ref mut menu) => if let Some(state) = menu.update(ctx, &mut self.shared)?
+            {
+                self.state = state;
+            },
+            State::LevelSelect(ref mut ls) => if let Some(state) = ls.update(ctx, &mut self.shared)?
             {
                 self.state = state;
             },
-            State::LevelSelect(ref mut ls) => if let Some(state) = ls.update(ctx)?
+            State::Game(ref mut game) => if let Some(state) = game.update(ctx, &mut self.shared)?
             {
                 self.state = state;
             },
-            State::Game(ref mut game) => if let Some(state) = game.update(ctx)?
+            State::LevelClear(ref mut lc) => if let Some(state) = lc.update(ctx, &mut self.shared)?
             {
                 self.state = state;
             },
-            State::LevelClear(ref mut lc) => if let Some(state) = lc.update(ctx)?
+            State::LevelLose(ref mut ll) => if let Some(state) = ll.update(ctx, &mut self.shared)?
             {
                 self.state = state;
             },
-            State::LevelLose(ref mut ll) => if let Some(state) = ll.update(ctx)?
+            State::ReplaySelect(ref mut rs) => if let Some(state) = rs.update(ctx, &mut self.shared)?
+            {
+                self.state = state;
+            },
+            State::EndlessSelect(ref mut es) => if let Some(state) = es.update(ctx, &mut self.shared)?
+            {
+                self.state = state;
+            },
+            State::Daily(ref mut daily) => if let Some(state) = daily.update(ctx, &mut self.shared)?
+            {
+                self.state = state;
+            },
+            State::HighScores(ref mut hs) => if let Some(state) = hs.update(ctx, &mut self.shared)?
+            {
+                self.state = state;
+            },
+            State::Controls(ref mut controls) => if let Some(state) = controls.update(ctx, &mut self.shared)?
             {
                 self.state = state;
             },
@@ -64,12 +123,24 @@ impl EventHandler for Window
         Ok(())
     }
 
-    fn key_down_event(&mut self, _ctx: &mut Context, key: KeyCode, _mods: KeyMods, repeat: bool)
+    fn key_down_event(&mut self, ctx: &mut Context, key: KeyCode, _mods: KeyMods, repeat: bool)
     {
         match &mut self.state
         {
+            State::Menu(ref mut menu) => menu.key_down(key, repeat),
+            State::LevelSelect(ref mut ls) => ls.key_down(key, repeat),
             State::Game(ref mut game) => game.key_down(key, repeat),
-            _ => { },
+            State::LevelClear(ref mut lc) => lc.key_down(ctx, &mut self.shared, key, repeat),
+            State::LevelLose(ref mut ll) => ll.key_down(ctx, &mut self.shared, key, repeat),
+            State::ReplaySelect(ref mut rs) => rs.key_down(key, repeat),
+            State::EndlessSelect(ref mut es) => es.key_down(ctx, key, repeat),
+            State::Daily(ref mut daily) => daily.key_down(ctx, &mut self.shared, key, repeat),
+            State::HighScores(ref mut hs) => hs.key_down(key, repeat),
+            State::Controls(ref mut controls) =>
+                if let Err(e) = controls.key_down(ctx, &mut self.shared, key, repeat)
+                {
+                    eprintln!("warning: {}", e);
+                },
         }
     }
 
@@ -77,7 +148,72 @@ impl EventHandler for Window
     {
         match &mut self.state
         {
+            State::Menu(ref mut menu) => menu.key_up(key),
+            State::LevelSelect(ref mut ls) => ls.key_up(key),
             State::Game(ref mut game) => game.key_up(key),
+            State::LevelClear(ref mut lc) => lc.key_up(key),
+            State::LevelLose(ref mut ll) => ll.key_up(key),
+            State::ReplaySelect(ref mut rs) => rs.key_up(key),
+            State::EndlessSelect(ref mut es) => es.key_up(key),
+            State::Daily(ref mut daily) => daily.key_up(key),
+            State::HighScores(ref mut hs) => hs.key_up(key),
+            State::Controls(ref mut controls) => controls.key_up(key),
+        }
+    }
+
+    // in the game the gamepad goes to the same input as the keys, so the game doesn't
+    // need to know which one is being used. it also stands in for the menu keys
+    fn gamepad_button_down_event(&mut self, ctx: &mut Context, button: PadButton, _id: GamepadId)
+    {
+        if let State::Game(ref mut game) = self.state
+        {
+            game.pad_down(button);
+        }
+        if let Some(key) = input::pad_key(button)
+        {
+            self.pad_key(ctx, key, true);
+        }
+    }
+
+    fn gamepad_button_up_event(&mut self, ctx: &mut Context, button: PadButton, _id: GamepadId)
+    {
+        if let State::Game(ref mut game) = self.state
+        {
+            game.pad_up(button);
+        }
+        if let Some(key) = input::pad_key(button)
+        {
+            self.pad_key(ctx, key, false);
+        }
+    }
+
+    fn gamepad_axis_event(&mut self, ctx: &mut Context, axis: Axis, value: f32, _id: GamepadId)
+    {
+        if let State::Game(ref mut game) = self.state
+        {
+            game.pad_axis(axis, value);
+        }
+
+        let (released, pressed) = self.stick_keys.axis(axis, value);
+        if let Some(key) = released
+        {
+            self.pad_key(ctx, key, false);
+        }
+        if let Some(key) = pressed
+        {
+            self.pad_key(ctx, key, true);
+        }
+    }
+
+    // typed characters, for text boxes
+    fn text_input_event(&mut self, ctx: &mut Context, ch: char)
+    {
+        match &mut self.state
+        {
+            State::LevelClear(ref mut lc) => lc.text_input(ctx, ch),
+            State::LevelLose(ref mut ll) => ll.text_input(ctx, ch),
+            State::Daily(ref mut daily) => daily.text_input(ctx, ch),
+            State::EndlessSelect(ref mut es) => es.text_input(ctx, ch),
             _ => { },
         }
     }
@@ -91,6 +227,11 @@ impl EventHandler for Window
             State::Game(ref mut game) => game.mouse_move(x, y),
             State::LevelClear(ref mut lc) => lc.mouse_move(x, y),
             State::LevelLose(ref mut ll) => ll.mouse_move(x, y),
+            State::ReplaySelect(ref mut rs) => rs.mouse_move(x, y),
+            State::EndlessSelect(ref mut es) => es.mouse_move(x, y),
+            State::Daily(ref mut daily) => daily.mouse_move(x, y),
+            State::HighScores(ref mut hs) => hs.mouse_move(x, y),
+            State::Controls(ref mut controls) => controls.mouse_move(x, y),
         }
     }
 
@@ -108,6 +249,11 @@ impl EventHandler for Window
             State::Game(ref mut game) => game.mouse_down(button, x, y),
             State::LevelClear(ref mut lc) => lc.mouse_down(button, x, y),
             State::LevelLose(ref mut ll) => ll.mouse_down(button, x, y),
+            State::ReplaySelect(ref mut rs) => rs.mouse_down(button, x, y),
+            State::EndlessSelect(ref mut es) => es.mouse_down(button, x, y),
+            State::Daily(ref mut daily) => daily.mouse_down(button, x, y),
+            State::HighScores(ref mut hs) => hs.mouse_down(button, x, y),
+            State::Controls(ref mut controls) => controls.mouse_down(button, x, y),
         }
     }
 
@@ -125,6 +271,11 @@ impl EventHandler for Window
             State::Game(ref mut game) => game.mouse_up(button),
             State::LevelClear(ref mut lc) => lc.mouse_up(button),
             State::LevelLose(ref mut ll) => ll.mouse_up(button),
+            State::ReplaySelect(ref mut rs) => rs.mouse_up(button),
+            State::EndlessSelect(ref mut es) => es.mouse_up(button),
+            State::Daily(ref mut daily) => daily.mouse_up(button),
+            State::HighScores(ref mut hs) => hs.mouse_up(button),
+            State::Controls(ref mut controls) => controls.mouse_up(button),
         }
     }
 
@@ -141,6 +292,11 @@ impl EventHandler for Window
             State::Game(ref mut game) => game.draw(ctx)?,
             State::LevelClear(ref mut lc) => lc.draw(ctx)?,
             State::LevelLose(ref mut ll) => ll.draw(ctx)?,
+            State::ReplaySelect(ref mut rs) => rs.draw(ctx)?,
+            State::EndlessSelect(ref mut es) => es.draw(ctx)?,
+            State::Daily(ref mut daily) => daily.draw(ctx)?,
+            State::HighScores(ref mut hs) => hs.draw(ctx)?,
+            State::Controls(ref mut controls) => controls.draw(ctx)?,
         }
 
         graphics::present(ctx)